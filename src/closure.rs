@@ -0,0 +1,162 @@
+//
+// Congruence closure: starting from x == x * x for every element,
+// plus any extra relations we've been given, find the smallest
+// equivalence that also respects + and *.
+//
+// An equivalence respects the operations exactly when it is preserved
+// by the "translations" x -> x + c, x -> c * x and x -> x * c, for
+// every constant c. Every element is a sum of basis words, and every
+// word is a product of generators, so it's enough to check adding a
+// basis word and multiplying on either side by a or b - the rest are
+// compositions of those.
+//
+// Moreover, the translations only need applying to the pairs that
+// actually caused two classes to merge: everything else in a class
+// is linked to its representative by a chain of such pairs. So we
+// keep a worklist of merged pairs, and for each one union the images
+// under every translation, adding any new merges to the worklist. We
+// work through it a generation at a time, which makes for a
+// meaningful count of passes.
+//
+// Each element can be merged away at most once, so this touches a
+// few hundred thousand elements, rather than the ~2^28 pairs a
+// pass over the whole table would.
+//
+// Each merge is recorded along with its cause - a square, one of the
+// extra relations, or a translation of an earlier merge - so that we
+// can later explain why two elements are equal.
+//
+
+use std::time::{Duration, Instant};
+
+use crate::rig::{Rig, A, B, NUM_RIGS};
+use crate::union::{Merge, Reason, RigUnion};
+
+// An elementary translation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Translation {
+    // x -> x + c
+    Add(Rig),
+    // x -> c * x
+    MulLeft(Rig),
+    // x -> x * c
+    MulRight(Rig),
+}
+
+impl Translation {
+    pub fn apply(&self, x: &Rig) -> Rig {
+        match self {
+            Translation::Add(c) => x.add(c),
+            Translation::MulLeft(c) => c.mul(x),
+            Translation::MulRight(c) => x.mul(c),
+        }
+    }
+}
+
+// Adding each basis word, and multiplying by a or b on either side.
+fn translations() -> Vec<Translation> {
+    let mut v = Rig::basis()
+        .into_iter()
+        .map(Translation::Add)
+        .collect::<Vec<_>>();
+    for g in [A, B] {
+        v.push(Translation::MulLeft(g.clone()));
+        v.push(Translation::MulRight(g));
+    }
+    v
+}
+
+// One phase of the closure, for reporting progress.
+#[derive(Clone, Debug)]
+pub struct Phase {
+    pub name: String,
+    // Number of pairs of classes merged.
+    pub merges: usize,
+    // Number of classes left afterwards.
+    pub classes: usize,
+    pub elapsed: Duration,
+}
+
+// How the closure went: seeding with squares and relations, and then
+// a pass per generation of the worklist. Pass n handles the merges
+// made by pass n - 1, and the last makes none.
+#[derive(Clone, Debug, Default)]
+pub struct ClosureStats {
+    pub phases: Vec<Phase>,
+    pub elapsed: Duration,
+}
+
+impl ClosureStats {
+    fn record(&mut self, name: String, merges: usize, union: &RigUnion, start: Instant) {
+        self.phases.push(Phase {
+            name,
+            merges,
+            classes: union.class_count(),
+            elapsed: start.elapsed(),
+        });
+    }
+
+    // Number of passes over the worklist, after seeding.
+    pub fn passes(&self) -> usize {
+        self.phases.len().saturating_sub(2)
+    }
+
+    pub fn merges(&self) -> usize {
+        self.phases.iter().map(|p| p.merges).sum()
+    }
+}
+
+// Run the closure over all elements, with any extra relations,
+// returning the finished union-find structure, which records why each
+// merge happened, and how it went.
+pub fn close(relations: &[(Rig, Rig)]) -> (RigUnion, ClosureStats) {
+    let start = Instant::now();
+    let mut stats = ClosureStats::default();
+    let mut equiv_classes = RigUnion::new();
+    let mut worklist = Vec::new();
+
+    // First of all, identify all rigs with their squares, and impose
+    // the extra relations.
+    let phase_start = Instant::now();
+    for i in 0..NUM_RIGS {
+        let rig = Rig::from(i);
+        let rigrig = rig.mul(&rig);
+        if equiv_classes.union(&rig, &rigrig, Reason::Square) {
+            worklist.push(equiv_classes.merges().len() - 1);
+        }
+    }
+    let squares = worklist.len();
+    stats.record("squares".to_string(), squares, &equiv_classes, phase_start);
+
+    let phase_start = Instant::now();
+    for (k, (x, y)) in relations.iter().enumerate() {
+        if equiv_classes.union(x, y, Reason::Relation(k)) {
+            worklist.push(equiv_classes.merges().len() - 1);
+        }
+    }
+    let merges = worklist.len() - squares;
+    stats.record("relations".to_string(), merges, &equiv_classes, phase_start);
+
+    // Then make sure that if x == y, the translations of x and y are
+    // equal too.
+    let translations = translations();
+    while !worklist.is_empty() {
+        let phase_start = Instant::now();
+        let mut next = Vec::new();
+        for m in worklist {
+            let Merge { lhs, rhs, .. } = equiv_classes.merge(m).clone();
+            for t in translations.iter() {
+                let reason = Reason::Congruence(m, t.clone());
+                if equiv_classes.union(&t.apply(&lhs), &t.apply(&rhs), reason) {
+                    next.push(equiv_classes.merges().len() - 1);
+                }
+            }
+        }
+        let name = format!("pass {}", stats.passes() + 1);
+        stats.record(name, next.len(), &equiv_classes, phase_start);
+        worklist = next;
+    }
+
+    stats.elapsed = start.elapsed();
+    (equiv_classes, stats)
+}
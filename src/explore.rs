@@ -0,0 +1,280 @@
+//
+// The free idempotent rig over any finite monoid basis, rather than
+// just the seven words in a and b.
+//
+// Elements are sums of monoid elements, stored as a vector of
+// coefficients, reduced to 0..3 in the same way as `Rig`. With the
+// free band on n generators as the monoid, the quotient is the free
+// idempotent rig on n generators.
+//
+// There are 4^m sums over an m-element monoid. We start from 0 and 1
+// and discover sums as the closure needs them, giving up once a
+// user-chosen number have been found, but don't be fooled: the
+// closure only finishes once it has found every one of the 4^m sums.
+// Every merged pair has all its translations added, and adding monoid
+// elements to 0 reaches everything. So this saves building the table
+// up front, but nothing more, and three generators (4^160 sums) are
+// out of reach. If the closure is cut short, the classes found so far
+// come from a partial closure, and aren't a bound on anything. See
+// `closure.rs` for the algorithm.
+//
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::monoid::Monoid;
+use crate::rig::fold;
+use crate::union::UnionFind;
+
+// The monoid rig: sums of monoid elements, with coefficients in 0..3.
+#[derive(Clone, Debug)]
+pub struct MonoidRig {
+    monoid: Monoid,
+}
+
+impl MonoidRig {
+    pub fn new(monoid: Monoid) -> MonoidRig {
+        MonoidRig { monoid }
+    }
+
+    pub fn monoid(&self) -> &Monoid {
+        &self.monoid
+    }
+
+    pub fn zero(&self) -> Vec<u8> {
+        vec![0; self.monoid.len()]
+    }
+
+    // A single monoid element, as a sum.
+    pub fn basis(&self, x: usize) -> Vec<u8> {
+        let mut v = self.zero();
+        v[x] = 1;
+        v
+    }
+
+    pub fn one(&self) -> Vec<u8> {
+        self.basis(self.monoid.identity())
+    }
+
+    pub fn add(&self, x: &[u8], y: &[u8]) -> Vec<u8> {
+        x.iter()
+            .zip(y)
+            .map(|(&i, &j)| fold(i as usize + j as usize) as u8)
+            .collect()
+    }
+
+    pub fn mul(&self, x: &[u8], y: &[u8]) -> Vec<u8> {
+        let mut acc = vec![0usize; self.monoid.len()];
+        for (i, &ci) in x.iter().enumerate().filter(|(_, &c)| c != 0) {
+            for (j, &cj) in y.iter().enumerate().filter(|(_, &c)| c != 0) {
+                acc[self.monoid.mul(i, j)] += ci as usize * cj as usize;
+            }
+        }
+        acc.into_iter().map(|c| fold(c) as u8).collect()
+    }
+
+    // Print in the same style as `Rig`, e.g. "2 + a + 3bc".
+    pub fn format(&self, x: &[u8]) -> String {
+        let mut v = Vec::new();
+        for (i, &c) in x.iter().enumerate() {
+            let name = if i == self.monoid.identity() {
+                ""
+            } else {
+                self.monoid.name(i)
+            };
+            if c == 1 && !name.is_empty() {
+                v.push(name.to_string());
+            } else if c != 0 {
+                v.push(format!("{}{}", c, name));
+            }
+        }
+        if v.is_empty() {
+            "0".to_string()
+        } else {
+            v.join(" + ")
+        }
+    }
+}
+
+// The state of the closure so far.
+#[derive(Clone, Debug)]
+pub struct Exploration {
+    // Every sum discovered, indexed as in `union`.
+    elements: Vec<Vec<u8>>,
+    union: UnionFind,
+    complete: bool,
+}
+
+impl Exploration {
+    // Whether the closure finished. If not, the classes are those of a
+    // partial closure, which say nothing about the answer.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    // Number of distinct sums discovered.
+    pub fn explored(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn class_count(&self) -> usize {
+        self.union.class_count()
+    }
+
+    // The discovered sums, grouped into equivalence classes. Classes
+    // are sorted by size, then minimal element, and each class is
+    // sorted, as in `Quotient`.
+    pub fn classes(&self) -> Vec<Vec<&[u8]>> {
+        let mut classes = self
+            .union
+            .get_classes()
+            .into_iter()
+            .map(|class| {
+                let mut class = class
+                    .into_iter()
+                    .map(|i| self.elements[i].as_slice())
+                    .collect::<Vec<_>>();
+                class.sort();
+                class
+            })
+            .collect::<Vec<_>>();
+        classes.sort_by(|x, y| (x.len(), x[0]).cmp(&(y.len(), y[0])));
+        classes
+    }
+}
+
+struct Explorer<'a> {
+    rig: &'a MonoidRig,
+    cap: usize,
+    elements: Vec<Vec<u8>>,
+    index: HashMap<Vec<u8>, usize>,
+    union: UnionFind,
+    // Whether some member of the class has had its square and
+    // translations added. Only meaningful at class representatives.
+    expanded: Vec<bool>,
+    // Newly-discovered elements, waiting to be expanded.
+    queue: VecDeque<usize>,
+    // Pairs that merged two classes, whose translations need
+    // identifying.
+    worklist: Vec<(usize, usize)>,
+    // The constants for the elementary translations.
+    words: Vec<Vec<u8>>,
+    generators: Vec<Vec<u8>>,
+}
+
+impl Explorer<'_> {
+    // Find the index of a sum, adding it if it's new. Returns None if
+    // that would exceed the cap.
+    fn intern(&mut self, v: Vec<u8>) -> Option<usize> {
+        if let Some(&idx) = self.index.get(&v) {
+            return Some(idx);
+        }
+        if self.elements.len() == self.cap {
+            return None;
+        }
+        let idx = self.union.push();
+        self.index.insert(v.clone(), idx);
+        self.elements.push(v);
+        self.expanded.push(false);
+        self.queue.push_back(idx);
+        Some(idx)
+    }
+
+    fn union(&mut self, x: usize, y: usize) {
+        let (rx, ry) = (self.union.find(x), self.union.find(y));
+        if self.union.union(x, y) {
+            let expanded = self.expanded[rx] || self.expanded[ry];
+            self.expanded[self.union.find(x)] = expanded;
+            self.worklist.push((x, y));
+        }
+    }
+
+    // The images of an element under every elementary translation.
+    fn translations(&self, x: usize) -> Vec<Vec<u8>> {
+        let x = &self.elements[x];
+        let mut v = self
+            .words
+            .iter()
+            .map(|w| self.rig.add(x, w))
+            .collect::<Vec<_>>();
+        for g in self.generators.iter() {
+            v.push(self.rig.mul(g, x));
+            v.push(self.rig.mul(x, g));
+        }
+        v
+    }
+
+    // Run until done, or the cap is hit, returning whether we finished.
+    fn run(&mut self) -> Option<()> {
+        let zero = self.rig.zero();
+        let one = self.rig.one();
+        self.intern(zero)?;
+        self.intern(one)?;
+
+        loop {
+            // Keep the equivalence a congruence...
+            while let Some((x, y)) = self.worklist.pop() {
+                let tx = self.translations(x);
+                let ty = self.translations(y);
+                for (x, y) in tx.into_iter().zip(ty) {
+                    let x = self.intern(x)?;
+                    let y = self.intern(y)?;
+                    self.union(x, y);
+                }
+            }
+
+            // ... and then make sure every class has one element with
+            // its square identified and its translations known.
+            let Some(x) = self.queue.pop_front() else {
+                return Some(());
+            };
+            let root = self.union.find(x);
+            if self.expanded[root] {
+                continue;
+            }
+            self.expanded[root] = true;
+            let square = self.rig.mul(&self.elements[x], &self.elements[x]);
+            let square = self.intern(square)?;
+            self.union(x, square);
+            for t in self.translations(x) {
+                self.intern(t)?;
+            }
+        }
+    }
+}
+
+// Compute the free idempotent rig over the monoid, discovering at most
+// `cap` sums. This can only finish if the cap is at least 4^m, for an
+// m-element monoid.
+//
+// When the closure finishes, every discovered class has had its
+// square and translations identified, and every merged pair has had
+// its translations identified, which means the discovered sums are
+// closed under the translations. Since every sum can be reached from
+// 0 by adding monoid elements, that means every sum has been
+// discovered, and the result is the same as running the closure over
+// all of them.
+pub fn explore(rig: &MonoidRig, cap: usize) -> Exploration {
+    let mut explorer = Explorer {
+        rig,
+        cap,
+        elements: Vec::new(),
+        index: HashMap::new(),
+        union: UnionFind::new(0),
+        expanded: Vec::new(),
+        queue: VecDeque::new(),
+        worklist: Vec::new(),
+        words: (0..rig.monoid.len()).map(|x| rig.basis(x)).collect(),
+        generators: rig
+            .monoid
+            .generators()
+            .iter()
+            .map(|&g| rig.basis(g))
+            .collect(),
+    };
+    let complete = explorer.run().is_some();
+    Exploration {
+        elements: explorer.elements,
+        union: explorer.union,
+        complete,
+    }
+}
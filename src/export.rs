@@ -0,0 +1,303 @@
+//
+// Writing the quotient's operation tables out for other tools.
+//
+// Everything here numbers the classes the way `Quotient::classes`
+// does, i.e. the order from `RigUnion::get_classes`: by size, then
+// smallest element. That's the ids printed by `lookup` and used in
+// the other outputs, except that GAP counts from 1, so class i is
+// element i + 1 there.
+//
+
+use std::io::{self, Write};
+
+use crate::quotient::{Op, Quotient};
+use crate::rig::{Rig, WORDS};
+
+// Magic number at the start of the binary format.
+pub const BINARY_MAGIC: &[u8; 4] = b"RIGT";
+
+// Write one operation's Cayley table as CSV. The first row and
+// column hold the class ids of the operands, and the cell in row x,
+// column y holds the class id of x op y. The top-left cell is the
+// operation's symbol.
+pub fn write_csv(quotient: &Quotient, op: Op, out: &mut impl Write) -> io::Result<()> {
+    let n = quotient.len();
+    let symbol = match op {
+        Op::Add => "+",
+        Op::Mul => "*",
+    };
+    let header = (0..n).map(|y| y.to_string()).collect::<Vec<_>>();
+    writeln!(out, "{},{}", symbol, header.join(","))?;
+    for x in 0..n {
+        let row = (0..n)
+            .map(|y| quotient.op(op, x, y).to_string())
+            .collect::<Vec<_>>();
+        writeln!(out, "{},{}", x, row.join(","))?;
+    }
+    Ok(())
+}
+
+// Write both Cayley tables in a compact binary form:
+//
+//  * the 4 bytes of `BINARY_MAGIC`,
+//  * the number of classes n, as a little-endian u32,
+//  * the n * n entries of the addition table, row by row, each a
+//    little-endian u16 class id,
+//  * the multiplication table, in the same way.
+pub fn write_binary(quotient: &Quotient, out: &mut impl Write) -> io::Result<()> {
+    let n = quotient.len();
+    assert!(n <= u16::MAX as usize + 1, "too many classes for u16 ids");
+    out.write_all(BINARY_MAGIC)?;
+    out.write_all(&(n as u32).to_le_bytes())?;
+    for op in [Op::Add, Op::Mul] {
+        for x in 0..n {
+            for y in 0..n {
+                out.write_all(&(quotient.op(op, x, y) as u16).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Quote a string for JSON. Our strings are all plain ASCII, but
+// escape properly anyway.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn json_list(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(", "))
+}
+
+// An element, as its coefficients on the basis words and as a string
+// that can be parsed back in.
+fn json_rig(rig: &Rig) -> String {
+    format!(
+        "{{\"coeffs\": {}, \"expr\": {}}}",
+        json_list(rig.coeffs().iter().map(|c| c.to_string())),
+        json_string(&rig.to_string())
+    )
+}
+
+// Write the whole quotient as JSON:
+//
+//  * "basis": the words the coefficients refer to, "1" first,
+//  * "classes": for each class, in id order, its "id", "size",
+//    "lex" and "small" representatives and all its "members", each
+//    element being an object with "coeffs" and "expr",
+//  * "add" and "mul": the Cayley tables on class ids, as arrays of
+//    rows, so that add[x][y] is the id of x + y.
+//
+// The layout is fixed, one member or table row per line, so the
+// output diffs nicely.
+pub fn write_json(quotient: &Quotient, out: &mut impl Write) -> io::Result<()> {
+    let n = quotient.len();
+    let basis = WORDS
+        .iter()
+        .map(|w| json_string(if w.is_empty() { "1" } else { w }));
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"basis\": {},", json_list(basis))?;
+    writeln!(out, "  \"classes\": [")?;
+    for (id, class) in quotient.classes().iter().enumerate() {
+        writeln!(out, "    {{")?;
+        writeln!(out, "      \"id\": {},", id)?;
+        writeln!(out, "      \"size\": {},", class.len())?;
+        let lex = quotient.lex_representative(id);
+        writeln!(out, "      \"lex\": {},", json_rig(lex))?;
+        let small = quotient.small_representative(id);
+        writeln!(out, "      \"small\": {},", json_rig(small))?;
+        writeln!(out, "      \"members\": [")?;
+        for (i, rig) in class.iter().enumerate() {
+            let sep = if i + 1 < class.len() { "," } else { "" };
+            writeln!(out, "        {}{}", json_rig(rig), sep)?;
+        }
+        writeln!(out, "      ]")?;
+        writeln!(out, "    }}{}", if id + 1 < n { "," } else { "" })?;
+    }
+    writeln!(out, "  ],")?;
+    for op in [Op::Add, Op::Mul] {
+        let name = match op {
+            Op::Add => "add",
+            Op::Mul => "mul",
+        };
+        writeln!(out, "  \"{}\": [", name)?;
+        for x in 0..n {
+            let row = json_list((0..n).map(|y| quotient.op(op, x, y).to_string()));
+            writeln!(out, "    {}{}", row, if x + 1 < n { "," } else { "" })?;
+        }
+        let sep = if matches!(op, Op::Add) { "," } else { "" };
+        writeln!(out, "  ]{}", sep)?;
+    }
+    writeln!(out, "}}")
+}
+
+// The tables as nested lists, in the syntax GAP and Python share,
+// with `offset` added to every id.
+fn list_table(quotient: &Quotient, op: Op, offset: usize, indent: &str) -> String {
+    let n = quotient.len();
+    let rows = (0..n)
+        .map(|x| {
+            let row = (0..n).map(|y| (quotient.op(op, x, y) + offset).to_string());
+            format!("{}  [{}]", indent, row.collect::<Vec<_>>().join(", "))
+        })
+        .collect::<Vec<_>>();
+    format!("[\n{}\n{}]", rows.join(",\n"), indent)
+}
+
+// Names for the elements: the smallest element of each class, which
+// reads best. Quoted the same way in GAP and Python.
+fn name_list(quotient: &Quotient) -> String {
+    let names = (0..quotient.len())
+        .map(|id| json_string(&quotient.small_representative(id).to_string()))
+        .collect::<Vec<_>>();
+    format!("[{}]", names.join(", "))
+}
+
+// Write a GAP script defining the rig by its tables.
+//
+// GAP has no built-in semirings, so this defines a small
+// `RigByTables` in the style of `SemigroupByMultiplicationTable`,
+// returning a record with the tables, the element names, the ids of
+// 0, 1, a and b, and the additive semigroup and multiplicative monoid
+// as GAP objects, ready for the semigroup machinery.
+pub fn write_gap(quotient: &Quotient, out: &mut impl Write) -> io::Result<()> {
+    let id = |rig: &str| quotient.class_of(&rig.parse().unwrap()) + 1;
+    writeln!(
+        out,
+        "# The free idempotent rig on a and b, with {} elements.",
+        quotient.len()
+    )?;
+    writeln!(
+        out,
+        "# Element i is class i - 1 of the `rig` tool's output."
+    )?;
+    writeln!(out)?;
+    writeln!(out, "RigByTables := function(add, mul, names, zero, one)")?;
+    writeln!(out, "  return rec(")?;
+    writeln!(out, "    size := Length(add),")?;
+    writeln!(out, "    add := add,")?;
+    writeln!(out, "    mul := mul,")?;
+    writeln!(out, "    names := names,")?;
+    writeln!(out, "    zero := zero,")?;
+    writeln!(out, "    one := one,")?;
+    writeln!(out, "    additive := SemigroupByMultiplicationTable(add),")?;
+    writeln!(
+        out,
+        "    multiplicative := MonoidByMultiplicationTable(mul));"
+    )?;
+    writeln!(out, "end;")?;
+    writeln!(out)?;
+    writeln!(out, "rigNames := {};", name_list(quotient))?;
+    writeln!(out)?;
+    writeln!(out, "rigAdd := {};", list_table(quotient, Op::Add, 1, ""))?;
+    writeln!(out)?;
+    writeln!(out, "rigMul := {};", list_table(quotient, Op::Mul, 1, ""))?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "rig := RigByTables(rigAdd, rigMul, rigNames, {}, {});",
+        id("0"),
+        id("1")
+    )?;
+    writeln!(out, "rig.a := {};", id("a"))?;
+    writeln!(out, "rig.b := {};", id("b"))?;
+    Ok(())
+}
+
+// Write a Sage script defining the rig as a parent in the category of
+// finite semirings, with elements wrapping class ids, so Sage's
+// generic code (and `TestSuite`) can work with it.
+pub fn write_sage(quotient: &Quotient, out: &mut impl Write) -> io::Result<()> {
+    let id = |rig: &str| quotient.class_of(&rig.parse().unwrap());
+    writeln!(
+        out,
+        "# The free idempotent rig on a and b, with {} elements.",
+        quotient.len()
+    )?;
+    writeln!(out, "# Element i is class i of the `rig` tool's output.")?;
+    writeln!(out)?;
+    writeln!(out, "from sage.categories.semirings import Semirings")?;
+    writeln!(
+        out,
+        "from sage.structure.element_wrapper import ElementWrapper"
+    )?;
+    writeln!(out, "from sage.structure.parent import Parent")?;
+    writeln!(
+        out,
+        "from sage.structure.unique_representation import UniqueRepresentation"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "NAMES = {}", name_list(quotient))?;
+    writeln!(out)?;
+    writeln!(out, "ADD = {}", list_table(quotient, Op::Add, 0, ""))?;
+    writeln!(out)?;
+    writeln!(out, "MUL = {}", list_table(quotient, Op::Mul, 0, ""))?;
+    writeln!(out)?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "class FreeIdempotentRig(UniqueRepresentation, Parent):"
+    )?;
+    writeln!(out, "    def __init__(self):")?;
+    writeln!(
+        out,
+        "        Parent.__init__(self, category=Semirings().Finite())"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "    def _repr_(self):")?;
+    writeln!(out, "        return \"Free idempotent rig on a, b\"")?;
+    writeln!(out)?;
+    writeln!(out, "    def _element_constructor_(self, i):")?;
+    writeln!(out, "        return self.element_class(self, int(i))")?;
+    writeln!(out)?;
+    writeln!(out, "    def __iter__(self):")?;
+    writeln!(out, "        return (self(i) for i in range(len(NAMES)))")?;
+    writeln!(out)?;
+    writeln!(out, "    def cardinality(self):")?;
+    writeln!(out, "        return Integer(len(NAMES))")?;
+    writeln!(out)?;
+    writeln!(out, "    def an_element(self):")?;
+    writeln!(out, "        return self({})", id("a"))?;
+    writeln!(out)?;
+    writeln!(out, "    def zero(self):")?;
+    writeln!(out, "        return self({})", id("0"))?;
+    writeln!(out)?;
+    writeln!(out, "    def one(self):")?;
+    writeln!(out, "        return self({})", id("1"))?;
+    writeln!(out)?;
+    writeln!(out, "    def gens(self):")?;
+    writeln!(out, "        return (self({}), self({}))", id("a"), id("b"))?;
+    writeln!(out)?;
+    writeln!(out, "    class Element(ElementWrapper):")?;
+    writeln!(out, "        wrapped_class = int")?;
+    writeln!(out)?;
+    writeln!(out, "        def _add_(self, other):")?;
+    writeln!(
+        out,
+        "            return self.parent()(ADD[self.value][other.value])"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "        def _mul_(self, other):")?;
+    writeln!(
+        out,
+        "            return self.parent()(MUL[self.value][other.value])"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "        def _repr_(self):")?;
+    writeln!(out, "            return NAMES[self.value]")?;
+    writeln!(out)?;
+    writeln!(out)?;
+    writeln!(out, "R = FreeIdempotentRig()")?;
+    writeln!(out, "a, b = R.gens()")?;
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//
+// Green's relations on the multiplicative monoid of the quotient.
+//
+// Two elements are R-related if they generate the same right ideal
+// (xS = yS), L-related if they generate the same left ideal (Sx =
+// Sy), and J-related if they generate the same two-sided ideal (SxS =
+// SyS). H is the intersection of L and R, and D is their join, which
+// is the same as J in a finite monoid. Each D-class is a grid of
+// H-classes, with a row per R-class and a column per L-class, which
+// is what an egg-box diagram draws.
+//
+// Since every element is idempotent, the multiplicative monoid is a
+// band, so the H-classes are all single elements and each D-class is
+// a rectangular band. We don't rely on that, though: everything is
+// computed from the ideals, which makes a decent check.
+//
+
+use std::collections::HashMap;
+
+use crate::quotient::Quotient;
+
+// A set of class ids, as a bitmap.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct IdSet(Vec<u64>);
+
+impl IdSet {
+    fn new(n: usize) -> IdSet {
+        IdSet(vec![0; n.div_ceil(64)])
+    }
+
+    fn insert(&mut self, id: usize) {
+        self.0[id / 64] |= 1 << (id % 64);
+    }
+
+    fn is_subset(&self, other: &IdSet) -> bool {
+        self.0.iter().zip(other.0.iter()).all(|(x, y)| x & !y == 0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|x| x.count_ones() as usize).sum()
+    }
+}
+
+// Group the elements by some key, returning the groups, each in
+// increasing order and ordered by their smallest element, and the
+// index of each element's group.
+fn group_by<K: Eq + std::hash::Hash>(keys: &[K]) -> (Vec<Vec<usize>>, Vec<usize>) {
+    let mut index: HashMap<&K, usize> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of = Vec::with_capacity(keys.len());
+    for (id, key) in keys.iter().enumerate() {
+        let g = *index.entry(key).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[g].push(id);
+        group_of.push(g);
+    }
+    (groups, group_of)
+}
+
+// The classes of each of Green's relations, as lists of class ids of
+// the quotient.
+#[derive(Clone, Debug)]
+pub struct Green {
+    pub l_classes: Vec<Vec<usize>>,
+    pub r_classes: Vec<Vec<usize>>,
+    pub h_classes: Vec<Vec<usize>>,
+    // The D-classes, which are also the J-classes, ordered from the
+    // top of the J-order (the class of 1) down, by the size of their
+    // ideals.
+    pub d_classes: Vec<Vec<usize>>,
+    // The index of each element's class, for each relation.
+    l_of: Vec<usize>,
+    r_of: Vec<usize>,
+    h_of: Vec<usize>,
+    d_of: Vec<usize>,
+    // The covering relation of the J-order on D-classes: (i, j) means
+    // D-class j is directly below D-class i.
+    pub covers: Vec<(usize, usize)>,
+}
+
+impl Green {
+    pub fn new(quotient: &Quotient) -> Green {
+        let n = quotient.len();
+        let right_ideal = |x: usize| {
+            let mut ideal = IdSet::new(n);
+            for s in 0..n {
+                ideal.insert(quotient.mul(x, s));
+            }
+            ideal
+        };
+        let left_ideal = |x: usize| {
+            let mut ideal = IdSet::new(n);
+            for s in 0..n {
+                ideal.insert(quotient.mul(s, x));
+            }
+            ideal
+        };
+        // SxS is the union of s(xS), and we only need each distinct
+        // xs once.
+        let two_sided_ideal = |x: usize| {
+            let mut right = (0..n).map(|t| quotient.mul(x, t)).collect::<Vec<_>>();
+            right.sort();
+            right.dedup();
+            let mut ideal = IdSet::new(n);
+            for s in 0..n {
+                for &xt in right.iter() {
+                    ideal.insert(quotient.mul(s, xt));
+                }
+            }
+            ideal
+        };
+
+        let rights = (0..n).map(right_ideal).collect::<Vec<_>>();
+        let lefts = (0..n).map(left_ideal).collect::<Vec<_>>();
+        let ideals = (0..n).map(two_sided_ideal).collect::<Vec<_>>();
+
+        let (r_classes, r_of) = group_by(&rights);
+        let (l_classes, l_of) = group_by(&lefts);
+        let both = (0..n).map(|x| (r_of[x], l_of[x])).collect::<Vec<_>>();
+        let (h_classes, h_of) = group_by(&both);
+
+        // Biggest ideals first, so the top of the order comes first.
+        let (mut d_classes, _) = group_by(&ideals);
+        d_classes.sort_by_key(|d| (std::cmp::Reverse(ideals[d[0]].len()), d[0]));
+        let mut d_of = vec![0; n];
+        for (i, d) in d_classes.iter().enumerate() {
+            for &x in d.iter() {
+                d_of[x] = i;
+            }
+        }
+
+        // i is above j when j's ideal is inside i's, and i covers j
+        // if nothing is strictly between them.
+        let d_ideals = d_classes.iter().map(|d| &ideals[d[0]]).collect::<Vec<_>>();
+        let m = d_classes.len();
+        let above = |i: usize, j: usize| i != j && d_ideals[j].is_subset(d_ideals[i]);
+        let mut covers = Vec::new();
+        for i in 0..m {
+            for j in 0..m {
+                if above(i, j) && !(0..m).any(|k| above(i, k) && above(k, j)) {
+                    covers.push((i, j));
+                }
+            }
+        }
+
+        Green {
+            l_classes,
+            r_classes,
+            h_classes,
+            d_classes,
+            l_of,
+            r_of,
+            h_of,
+            d_of,
+            covers,
+        }
+    }
+
+    // The index of the class containing an element, for each relation.
+    pub fn l_class(&self, x: usize) -> usize {
+        self.l_of[x]
+    }
+
+    pub fn r_class(&self, x: usize) -> usize {
+        self.r_of[x]
+    }
+
+    pub fn h_class(&self, x: usize) -> usize {
+        self.h_of[x]
+    }
+
+    pub fn d_class(&self, x: usize) -> usize {
+        self.d_of[x]
+    }
+
+    // The egg-box of a D-class: its R-classes as rows and L-classes
+    // as columns, with the H-class at each intersection. Rows and
+    // columns are ordered by their smallest element.
+    pub fn egg_box(&self, d: usize) -> Vec<Vec<Vec<usize>>> {
+        let members = &self.d_classes[d];
+        let mut rows = members.iter().map(|&x| self.r_of[x]).collect::<Vec<_>>();
+        let mut cols = members.iter().map(|&x| self.l_of[x]).collect::<Vec<_>>();
+        for v in [&mut rows, &mut cols] {
+            v.sort();
+            v.dedup();
+        }
+        let mut grid = vec![vec![Vec::new(); cols.len()]; rows.len()];
+        for &x in members.iter() {
+            let i = rows.binary_search(&self.r_of[x]).unwrap();
+            let j = cols.binary_search(&self.l_of[x]).unwrap();
+            grid[i][j].push(x);
+        }
+        grid
+    }
+}
@@ -0,0 +1,335 @@
+//
+// Writing the closure's equalities out as a Lean 4 file, so that
+// they can be checked by something other than this program.
+//
+// The file defines an `IdempotentRig` class with the rig axioms plus
+// x * x = x, and then proves one lemma per merge the closure made,
+// for arbitrary elements a and b of any idempotent rig. Each merge is
+// either x = x * x, which is an axiom, an extra relation, which
+// becomes a hypothesis of every lemma, or an earlier merge with
+// something added to or multiplied onto both sides, which follows
+// from the earlier lemma.
+//
+// The lemmas are stated in terms of the normal-form elements, written
+// out as sums of words, e.g. 2 + ab is `1 + 1 + a * b`. Getting from
+// the product or sum of two of those back to normal form is the
+// fiddly bit, done as a `calc` chain with two steps:
+//
+//  * expand: multiply everything out, and remove square factors from
+//    the resulting words, with `simp`, and then rearrange the sum
+//    into groups of equal words with `ac_rfl`;
+//  * fold: reduce any group of 4 or more copies of a word, using
+//    x + x + x + x = x + x (which is idempotency of x + x).
+//
+// There's no Mathlib dependency, so the basics are proved from the
+// axioms at the top of the file.
+//
+
+use std::io::{self, Write};
+
+use crate::closure::Translation;
+use crate::rig::{convolve, Rig, WORDS};
+use crate::union::{Reason, RigUnion};
+
+const PREAMBLE: &str = "\
+universe u
+
+class IdempotentRig (R : Type u) extends Add R, Mul R where
+  zero : R
+  one : R
+  add_assoc : ∀ x y z : R, x + y + z = x + (y + z)
+  add_comm : ∀ x y : R, x + y = y + x
+  zero_add : ∀ x : R, zero + x = x
+  mul_assoc : ∀ x y z : R, x * y * z = x * (y * z)
+  one_mul : ∀ x : R, one * x = x
+  mul_one : ∀ x : R, x * one = x
+  zero_mul : ∀ x : R, zero * x = zero
+  mul_zero : ∀ x : R, x * zero = zero
+  left_distrib : ∀ x y z : R, x * (y + z) = x * y + x * z
+  right_distrib : ∀ x y z : R, (x + y) * z = x * z + y * z
+  mul_self : ∀ x : R, x * x = x
+
+namespace IdempotentRig
+
+variable {R : Type u} [IdempotentRig R]
+
+instance : OfNat R 0 := ⟨IdempotentRig.zero⟩
+instance : OfNat R 1 := ⟨IdempotentRig.one⟩
+
+instance : Std.Associative (α := R) (· + ·) := ⟨IdempotentRig.add_assoc⟩
+instance : Std.Commutative (α := R) (· + ·) := ⟨IdempotentRig.add_comm⟩
+instance : Std.Associative (α := R) (· * ·) := ⟨IdempotentRig.mul_assoc⟩
+
+theorem zero_add' (x : R) : 0 + x = x := IdempotentRig.zero_add x
+theorem add_zero' (x : R) : x + 0 = x :=
+  (IdempotentRig.add_comm x 0).trans (zero_add' x)
+theorem zero_mul' (x : R) : 0 * x = 0 := IdempotentRig.zero_mul x
+theorem mul_zero' (x : R) : x * 0 = 0 := IdempotentRig.mul_zero x
+theorem one_mul' (x : R) : 1 * x = x := IdempotentRig.one_mul x
+theorem mul_one' (x : R) : x * 1 = x := IdempotentRig.mul_one x
+
+-- Removing squares from the end of a left-associated word.
+theorem sq_right (y x : R) : x * y * y = x * y :=
+  (IdempotentRig.mul_assoc x y y).trans
+    (congrArg (fun w : R => x * w) (IdempotentRig.mul_self y))
+
+theorem sq2 (y z : R) : y * z * y * z = y * z :=
+  calc y * z * y * z = y * z * (y * z) := IdempotentRig.mul_assoc (y * z) y z
+    _ = y * z := IdempotentRig.mul_self (y * z)
+
+theorem sq2_right (y z x : R) : x * y * z * y * z = x * y * z :=
+  calc x * y * z * y * z = x * (y * z * y * z) := by simp only [IdempotentRig.mul_assoc]
+    _ = x * (y * z) := by rw [sq2 y z]
+    _ = x * y * z := (IdempotentRig.mul_assoc x y z).symm
+
+-- Coefficients can be reduced: 4x = 2x.
+theorem four (x : R) : x + x + x + x = x + x :=
+  calc x + x + x + x = (x + x) + (x + x) := IdempotentRig.add_assoc (x + x) x x
+    _ = (x * x + x * x) + (x * x + x * x) := by rw [IdempotentRig.mul_self x]
+    _ = x * (x + x) + x * (x + x) := by rw [IdempotentRig.left_distrib x x x]
+    _ = (x + x) * (x + x) := (IdempotentRig.right_distrib x x (x + x)).symm
+    _ = x + x := IdempotentRig.mul_self (x + x)
+
+end IdempotentRig
+
+-- Multiply out, reduce words in a and b to 1, a, b, ab, ba, aba or
+-- bab, and rearrange. Unhygienic, so that it picks up the a and b of
+-- the lemma it's used in.
+set_option hygiene false in
+macro \"expand_rig\" : tactic =>
+  `(tactic| (try simp only [IdempotentRig.left_distrib, IdempotentRig.right_distrib,
+      IdempotentRig.zero_add', IdempotentRig.add_zero', IdempotentRig.zero_mul',
+      IdempotentRig.mul_zero', IdempotentRig.one_mul', IdempotentRig.mul_one',
+      ← IdempotentRig.mul_assoc, IdempotentRig.mul_self a, IdempotentRig.mul_self b,
+      IdempotentRig.sq_right a, IdempotentRig.sq_right b, IdempotentRig.sq2 a b,
+      IdempotentRig.sq2 b a, IdempotentRig.sq2_right a b, IdempotentRig.sq2_right b a])
+    <;> ac_rfl)
+
+-- Reduce groups of four or more equal words, and rearrange.
+macro \"fold_rig\" : tactic =>
+  `(tactic| (try simp only [IdempotentRig.four]) <;> ac_rfl)
+
+section
+
+variable {R : Type u} [IdempotentRig R]
+";
+
+// A word as a Lean term, e.g. `a * b * a`.
+fn word(w: usize) -> String {
+    if WORDS[w].is_empty() {
+        return "1".to_string();
+    }
+    let letters = WORDS[w].chars().map(|l| l.to_string());
+    letters.collect::<Vec<_>>().join(" * ")
+}
+
+// A sum with the given number of copies of each word, as a Lean term.
+fn sum(counts: &[usize; 7]) -> String {
+    let mut terms = Vec::new();
+    for (w, &c) in counts.iter().enumerate() {
+        for _ in 0..c {
+            terms.push(word(w));
+        }
+    }
+    if terms.is_empty() {
+        "0".to_string()
+    } else {
+        terms.join(" + ")
+    }
+}
+
+// An element as a Lean term.
+fn term(x: &Rig) -> String {
+    sum(&x.coeffs())
+}
+
+// The same sum, with the copies of each word bracketed together, ready
+// for `four`.
+fn grouped(counts: &[usize; 7]) -> String {
+    let mut groups = Vec::new();
+    for (w, &c) in counts.iter().enumerate() {
+        if c != 0 {
+            let mut single = [0; 7];
+            single[w] = c;
+            groups.push(format!("({})", sum(&single)));
+        }
+    }
+    groups.join(" + ")
+}
+
+fn sum_counts(x: &Rig, y: &Rig) -> [usize; 7] {
+    let mut acc = x.coeffs();
+    for (a, c) in acc.iter_mut().zip(y.coeffs()) {
+        *a += c;
+    }
+    acc
+}
+
+// Steps of a `calc` chain from an unreduced term, whose words occur
+// with the given counts, to the normal form, as (term, proof) pairs.
+fn normalise(from: &str, counts: &[usize; 7]) -> Vec<(String, String)> {
+    let nf = term(&Rig::from_coeffs(*counts).normalise());
+    if from == nf {
+        Vec::new()
+    } else if counts.iter().any(|&c| c >= 4) {
+        vec![
+            (grouped(counts), "by expand_rig".to_string()),
+            (nf, "by fold_rig".to_string()),
+        ]
+    } else {
+        vec![(nf, "by expand_rig".to_string())]
+    }
+}
+
+// The same steps, but from the normal form back to the unreduced term.
+// The tactics work on both sides of the goal, so each step keeps its
+// proof, and just goes the other way.
+fn denormalise(from: &str, counts: &[usize; 7]) -> Vec<(String, String)> {
+    let steps = normalise(from, counts);
+    let mut terms = vec![from.to_string()];
+    terms.extend(steps.iter().map(|(t, _)| t.clone()));
+    terms.pop();
+    terms
+        .into_iter()
+        .zip(steps)
+        .map(|(t, (_, proof))| (t, proof))
+        .rev()
+        .collect()
+}
+
+// Apply a translation to a Lean term, giving the term and a function
+// that does the same, for `congrArg`.
+fn translate(t: &Translation, x: &str) -> (String, String) {
+    match t {
+        Translation::Add(c) => (
+            format!("({}) + ({})", x, term(c)),
+            format!("fun x : R => x + ({})", term(c)),
+        ),
+        Translation::MulLeft(g) => (
+            format!("{} * ({})", term(g), x),
+            format!("fun x : R => {} * x", term(g)),
+        ),
+        Translation::MulRight(g) => (
+            format!("({}) * {}", x, term(g)),
+            format!("fun x : R => x * {}", term(g)),
+        ),
+    }
+}
+
+fn translation_counts(t: &Translation, x: &Rig) -> [usize; 7] {
+    match t {
+        Translation::Add(c) => sum_counts(x, c),
+        Translation::MulLeft(g) => convolve(&g.coeffs(), &x.coeffs()),
+        Translation::MulRight(g) => convolve(&x.coeffs(), &g.coeffs()),
+    }
+}
+
+fn write_calc(out: &mut impl Write, from: &str, steps: &[(String, String)]) -> io::Result<()> {
+    if steps.is_empty() {
+        return writeln!(out, "  rfl");
+    }
+    writeln!(out, "  calc {}", from)?;
+    for (t, proof) in steps.iter() {
+        writeln!(out, "    _ = {} := {}", t, proof)?;
+    }
+    Ok(())
+}
+
+// Write the Lean file. With no goals, there's a lemma for every merge
+// the closure made. Otherwise, there's a theorem for each goal, along
+// with just the merges needed to prove it. Goals that don't hold are
+// skipped, with a comment saying so.
+pub fn write_lean(
+    union: &RigUnion,
+    relations: &[(Rig, Rig)],
+    goals: &[(Rig, Rig)],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut chains = Vec::new();
+    for (x, y) in goals.iter() {
+        chains.push(union.explain(x, y));
+    }
+    let merges = if goals.is_empty() {
+        (0..union.merges().len()).collect::<Vec<_>>()
+    } else {
+        let used = chains.iter().flatten().flatten().map(|step| step.merge);
+        union.dependencies(&used.collect::<Vec<_>>())
+    };
+
+    // Every lemma is about generators a and b, and takes the extra
+    // relations as hypotheses.
+    let mut params = "(a b : R)".to_string();
+    let mut args = "a b".to_string();
+    for (k, (x, y)) in relations.iter().enumerate() {
+        params += &format!(" (h{} : {} = {})", k + 1, term(x), term(y));
+        args += &format!(" h{}", k + 1);
+    }
+
+    writeln!(
+        out,
+        "-- Equalities in the idempotent rig on two generators found by the\n\
+         -- closure: {} merges, and {} goals.\n",
+        merges.len(),
+        goals.len()
+    )?;
+    write!(out, "{}", PREAMBLE)?;
+
+    for m in merges {
+        let merge = union.merge(m);
+        let (lhs, rhs) = (term(&merge.lhs), term(&merge.rhs));
+        writeln!(out)?;
+        writeln!(out, "theorem merge_{} {} :", m, params)?;
+        writeln!(out, "    {} = {} :=", lhs, rhs)?;
+        match &merge.reason {
+            Reason::Square => {
+                let square = format!("({}) * ({})", lhs, lhs);
+                let counts = convolve(&merge.lhs.coeffs(), &merge.lhs.coeffs());
+                let mut steps = vec![(
+                    square.clone(),
+                    "(IdempotentRig.mul_self _).symm".to_string(),
+                )];
+                steps.extend(normalise(&square, &counts));
+                write_calc(out, &lhs, &steps)?;
+            }
+            Reason::Relation(k) => writeln!(out, "  h{}", k + 1)?,
+            Reason::Congruence(earlier, t) => {
+                let earlier_merge = union.merge(*earlier);
+                let (from, f) = translate(t, &term(&earlier_merge.lhs));
+                let (to, _) = translate(t, &term(&earlier_merge.rhs));
+                let mut steps = denormalise(&from, &translation_counts(t, &earlier_merge.lhs));
+                steps.push((
+                    to.clone(),
+                    format!("congrArg ({}) (merge_{} {})", f, earlier, args),
+                ));
+                steps.extend(normalise(&to, &translation_counts(t, &earlier_merge.rhs)));
+                write_calc(out, &lhs, &steps)?;
+            }
+        }
+    }
+
+    for (i, ((x, y), chain)) in goals.iter().zip(chains).enumerate() {
+        writeln!(out)?;
+        let Some(chain) = chain else {
+            writeln!(out, "-- {} = {} does not hold.", x, y)?;
+            continue;
+        };
+        writeln!(out, "-- {} = {}", x, y)?;
+        writeln!(out, "theorem goal_{} {} :", i + 1, params)?;
+        writeln!(out, "    {} = {} :=", term(x), term(y))?;
+        let steps = chain
+            .iter()
+            .map(|step| {
+                let proof = format!("merge_{} {}", step.merge, args);
+                let proof = if union.merge(step.merge).lhs == step.from {
+                    proof
+                } else {
+                    format!("({}).symm", proof)
+                };
+                (term(&step.to), proof)
+            })
+            .collect::<Vec<_>>();
+        write_calc(out, &term(x), &steps)?;
+    }
+
+    writeln!(out, "\nend")
+}
@@ -0,0 +1,52 @@
+//
+// Implementation of a idempotent free rig with two generators, as
+// described at
+//
+// https://mastodon.xyz/@johncarlosbaez@mathstodon.xyz/109544917481142671
+//
+// The binary is a thin wrapper around `compute_classes`, which does
+// all the work. `explore` does the same for other numbers of
+// generators.
+//
+
+pub mod band;
+mod closure;
+mod explore;
+pub mod export;
+mod green;
+pub mod lean;
+pub mod monoid;
+mod order;
+mod parse;
+pub mod provers;
+mod quotient;
+mod rig;
+mod semiring;
+pub mod separate;
+mod truncation;
+mod union;
+
+pub use closure::{ClosureStats, Phase, Translation};
+pub use explore::{explore, Exploration, MonoidRig};
+pub use green::Green;
+pub use monoid::Monoid;
+pub use order::NaturalOrder;
+pub use parse::{Expr, ParseError, Relation};
+pub use quotient::{ClassInfo, Element, Op, Quotient};
+pub use rig::{convolve, cost, fold, Rig, A, B, NUM_RIGS, ONE, WORDS, WORD_PRODUCTS, ZERO};
+pub use semiring::{BoolMatrix, Matrix, Semiring, Truncated};
+pub use truncation::{check_truncation, Truncation};
+pub use union::{Merge, Reason, RigUnion, Step, UnionFind};
+
+// Compute the free idempotent rig on two generators, as a quotient
+// of the normal-form elements.
+pub fn compute_classes() -> Quotient {
+    compute_presented(&[])
+}
+
+// Compute the idempotent rig on two generators with extra relations,
+// each a pair of elements to be made equal.
+pub fn compute_presented(relations: &[(Rig, Rig)]) -> Quotient {
+    let (union, stats) = closure::close(relations);
+    Quotient::new(union, stats)
+}
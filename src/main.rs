@@ -1,383 +1,694 @@
 //
-// Implementation of a idempotent free rig with two generators, as
-// described at
-//
-// https://mastodon.xyz/@johncarlosbaez@mathstodon.xyz/109544917481142671
+// Command-line front end for the `rig` library: compute the free
+// idempotent rig on two generators and print it in various ways.
 //
 
-use std::collections::HashMap;
-use std::fmt;
-
-const NUM_RIGS: usize = 4 * 4 * 4 * 4 * 4 * 4 * 4;
-
-// Implementation of a free rig with idempotency and two generators.
-#[derive(Clone, Eq, Hash, PartialEq, Ord, PartialOrd)]
-struct Rig {
-    i: usize,
-    a: usize,
-    b: usize,
-    ab: usize,
-    ba: usize,
-    aba: usize,
-    bab: usize,
-}
+use std::io::{self, BufWriter, StdoutLock, Write};
 
-const ZERO: Rig = Rig {
-    i: 0,
-    a: 0,
-    b: 0,
-    ab: 0,
-    ba: 0,
-    aba: 0,
-    bab: 0,
+use rig::{
+    check_truncation, compute_presented, explore, export, lean, provers, separate, ClosureStats,
+    Green, Monoid, MonoidRig, NaturalOrder, Op, Quotient, Reason, Relation, Rig, Translation,
 };
 
-impl fmt::Display for Rig {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if *self == ZERO {
-            return write!(f, "0");
-        }
+// How to pick the element that represents an equivalence class.
+#[derive(Clone, Copy)]
+enum Order {
+    // Lexicographically-first element, according to Rust's
+    // auto-generated comparison operator.
+    Lex,
+    // "Smallest" element, according to `cost`.
+    Small,
+}
 
-        fn elt(acc: &mut Vec<String>, i: usize, s: &str) {
-            if i == 1 && !s.is_empty() {
-                acc.push(s.to_string());
-            } else if i != 0 {
-                acc.push(format!("{}{}", i, s));
-            }
+// What to print once the equivalence classes have been computed.
+enum Command {
+    // All elements, one line per equivalence class.
+    Classes,
+    // One element per equivalence class.
+    Representatives(Order),
+    // Just the class sizes and the number of classes.
+    Summary,
+    // The equivalence class containing a given element, and whether
+    // to list all its members.
+    Lookup(Rig, bool),
+    // Why two elements are equal.
+    Explain(Rig, Rig),
+    // Lean proofs of the closure's merges, or just those needed to
+    // prove an equality.
+    Lean(Option<(Rig, Rig)>),
+    // A homomorphism showing two elements are distinct.
+    Separate(Rig, Rig),
+    // Homomorphisms showing every pair of classes is distinct.
+    SeparateAll,
+    // Rerun the closure without reducing coefficients, which run
+    // below the given bound, and compare.
+    Truncation(usize),
+    // Cayley table of one operation, as CSV.
+    TableCsv(Op),
+    // Both Cayley tables, in binary.
+    TableBinary,
+    // Everything, as JSON.
+    Json,
+    // Scripts defining the rig for GAP or Sage.
+    Gap,
+    Sage,
+    // A problem asking automated provers to show an identity, in
+    // Prover9's format or TPTP's.
+    Prover9(Relation),
+    Tptp(Relation),
+    // The tables, as a Mace4 model.
+    Mace4,
+    // Green's relations on the multiplicative monoid, with egg-box
+    // diagrams.
+    Green,
+    // The natural preorder on the additive monoid, summarised or as a
+    // GraphViz Hasse diagram.
+    Order(bool),
+    // The free idempotent rig on some number of generators, exploring
+    // at most the given number of sums.
+    Free(usize, usize),
+    // The free idempotent rig over the sums of elements of a monoid,
+    // exploring at most the given number of sums.
+    Monoid(Monoid, usize),
+}
+
+// Default limit on the number of sums `free` explores. The closure
+// needs all 4^m sums over an m-element monoid, so this covers up to 8
+// elements.
+const DEFAULT_CAP: usize = 100_000;
+
+// Limit on the number of generators for `free`. The free band with
+// identity has 160 elements on 3 generators, but 332381 on 4, whose
+// multiplication table would have over 10^11 entries.
+const MAX_GENERATORS: usize = 3;
+
+// Default and largest coefficient bounds for `truncation`. The
+// closure covers bound^7 sums, so 10 is already 10 million, with
+// about half a gigabyte of translations.
+const DEFAULT_BOUND: usize = 8;
+const MAX_BOUND: usize = 10;
+
+const USAGE: &str = "\
+Usage: rig [--quiet] [--relation <lhs = rhs>]... [COMMAND]
+
+Options:
+  --relation <lhs = rhs>               Impose an extra relation between two
+                                       expressions, e.g. \"ab = ba\", on the
+                                       two-generator rig. May be repeated
+  --quiet                              Don't report the closure's progress
+                                       on stderr
+
+Commands:
+  classes                              Print every element, one line per
+                                       equivalence class (the default)
+  representatives [--order lex|small]  Print one element per class, either
+                                       the lexicographically-first (the
+                                       default) or the smallest
+  summary                              Print class sizes and the total
+  lookup [--members] <expr>            Describe the class containing <expr>,
+                                       any sum of products of a, b, 0, 1
+                                       and parentheses, e.g.
+                                       \"(a + b)(a + b)ab\"; --members also
+                                       lists every element of the class
+  explain <x> <y>                      Show why <x> and <y> are equal, as a
+                                       chain of steps taken by the closure
+  lean [<x> <y>]                       Write a Lean 4 file proving every
+                                       merge the closure made, or just
+                                       those needed to show <x> = <y>
+  separate <x> <y>                     Show <x> and <y> are distinct, by
+                                       finding a homomorphism into a small
+                                       matrix rig that tells them apart
+  separate --all                       Find homomorphisms that between them
+                                       tell every pair of classes apart
+  truncation [--bound <k>]             Rerun the closure with coefficients
+                                       0..k-1 (default 8), without
+                                       reducing 4 to 2, and check it gives
+                                       the same rig
+  tables csv <add|mul>                 Print the Cayley table of + or * on
+                                       class ids, as CSV
+  tables binary                        Write both Cayley tables to stdout
+                                       in a compact binary form
+  json                                 Write every class, its members and
+                                       representatives, and both tables,
+                                       as JSON
+  gap                                  Write a GAP script defining the rig
+                                       by its tables, numbering elements
+                                       from 1
+  sage                                 Write a Sage script defining the
+                                       rig as a finite semiring
+  prover9 <lhs = rhs>                  Write a Prover9 problem: the axioms,
+                                       any relations, and the goal
+                                       <lhs = rhs>
+  tptp <lhs = rhs>                     The same, as a TPTP FOF problem
+  mace4                                Write the tables as a Mace4 model
+  green                                Print Green's relations on the
+                                       multiplicative monoid, the order
+                                       on J-classes, and egg-box diagrams
+  order [--dot]                        Describe the natural preorder, x <= y
+                                       iff y = x + z for some z, or write
+                                       its Hasse diagram for GraphViz
+  free <n> [--cap <max>]               Compute the free idempotent rig on n
+                                       generators instead. This needs all
+                                       4^m sums of the m words, so is
+                                       refused if that's more than <max>
+                                       (default 100000), which in practice
+                                       means n is at most 2
+  monoid <file> [--cap <max>]          The same, but over sums of elements of
+                                       the monoid whose Cayley table is in
+                                       <file>
+  help                                 Print this message";
+
+fn parse_rig(expr: &str) -> Result<Rig, String> {
+    expr.parse::<Rig>()
+        .map_err(|e| format!("can't parse '{}': {}", expr, e))
+}
+
+fn parse_generators(n: &str) -> Result<usize, String> {
+    match n.parse() {
+        Ok(n) if n <= MAX_GENERATORS => Ok(n),
+        Ok(_) => Err(format!(
+            "can't do {} generators: the free band with identity on 4 or more is \
+             too big to tabulate",
+            n
+        )),
+        _ => Err(format!(
+            "bad number of generators '{}', expected 0..{}",
+            n, MAX_GENERATORS
+        )),
+    }
+}
+
+fn parse_cap(cap: &str) -> Result<usize, String> {
+    cap.parse()
+        .map_err(|_| format!("bad cap '{}', expected a number", cap))
+}
+
+fn parse_bound(bound: &str) -> Result<usize, String> {
+    match bound.parse() {
+        Ok(k) if (4..=MAX_BOUND).contains(&k) => Ok(k),
+        _ => Err(format!("bad bound '{}', expected 4..{}", bound, MAX_BOUND)),
+    }
+}
+
+fn parse_goal(goal: &str) -> Result<Relation, String> {
+    Relation::parse_with(goal, "ab").map_err(|e| format!("can't parse '{}': {}", goal, e))
+}
+
+fn read_monoid(file: &str) -> Result<Monoid, String> {
+    let text =
+        std::fs::read_to_string(file).map_err(|e| format!("can't read '{}': {}", file, e))?;
+    text.parse()
+        .map_err(|e| format!("bad monoid in '{}': {}", file, e))
+}
+
+// Pull a flag out of the arguments, returning whether it was there.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+// Pull any "--relation <lhs = rhs>" options out of the arguments.
+fn take_relations(args: &mut Vec<String>) -> Result<Vec<Relation>, String> {
+    let mut relations = Vec::new();
+    while let Some(i) = args.iter().position(|arg| arg == "--relation") {
+        if i + 1 == args.len() {
+            return Err("--relation needs an argument".to_string());
         }
-        let mut v = Vec::new();
-        elt(&mut v, self.i, "");
-        elt(&mut v, self.a, "a");
-        elt(&mut v, self.b, "b");
-        elt(&mut v, self.ab, "ab");
-        elt(&mut v, self.ba, "ba");
-        elt(&mut v, self.aba, "aba");
-        elt(&mut v, self.bab, "bab");
-        write!(f, "{}", v.join(" + "))
+        let relation = args.remove(i + 1);
+        args.remove(i);
+        let parsed = Relation::parse_with(&relation, "ab")
+            .map_err(|e| format!("can't parse relation '{}': {}", relation, e))?;
+        relations.push(parsed);
+    }
+    Ok(relations)
+}
+
+// Parse the command line (excluding the program name). Arguments are
+// checked before the closure runs, so mistakes are reported quickly.
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] | ["classes"] => Ok(Command::Classes),
+        ["representatives"] => Ok(Command::Representatives(Order::Lex)),
+        ["representatives", "--order", order] => match *order {
+            "lex" => Ok(Command::Representatives(Order::Lex)),
+            "small" => Ok(Command::Representatives(Order::Small)),
+            _ => Err(format!("unknown order '{}', expected lex or small", order)),
+        },
+        ["summary"] => Ok(Command::Summary),
+        ["lookup", expr] => parse_rig(expr).map(|rig| Command::Lookup(rig, false)),
+        ["lookup", "--members", expr] => parse_rig(expr).map(|rig| Command::Lookup(rig, true)),
+        ["explain", x, y] => Ok(Command::Explain(parse_rig(x)?, parse_rig(y)?)),
+        ["lean"] => Ok(Command::Lean(None)),
+        ["lean", x, y] => Ok(Command::Lean(Some((parse_rig(x)?, parse_rig(y)?)))),
+        ["separate", "--all"] => Ok(Command::SeparateAll),
+        ["separate", x, y] => Ok(Command::Separate(parse_rig(x)?, parse_rig(y)?)),
+        ["truncation"] => Ok(Command::Truncation(DEFAULT_BOUND)),
+        ["truncation", "--bound", k] => Ok(Command::Truncation(parse_bound(k)?)),
+        ["tables", "csv", "add"] => Ok(Command::TableCsv(Op::Add)),
+        ["tables", "csv", "mul"] => Ok(Command::TableCsv(Op::Mul)),
+        ["tables", "binary"] => Ok(Command::TableBinary),
+        ["json"] => Ok(Command::Json),
+        ["gap"] => Ok(Command::Gap),
+        ["sage"] => Ok(Command::Sage),
+        ["prover9", goal] => Ok(Command::Prover9(parse_goal(goal)?)),
+        ["tptp", goal] => Ok(Command::Tptp(parse_goal(goal)?)),
+        ["mace4"] => Ok(Command::Mace4),
+        ["green"] => Ok(Command::Green),
+        ["order"] => Ok(Command::Order(false)),
+        ["order", "--dot"] => Ok(Command::Order(true)),
+        ["free", n] => Ok(Command::Free(parse_generators(n)?, DEFAULT_CAP)),
+        ["free", n, "--cap", cap] => Ok(Command::Free(parse_generators(n)?, parse_cap(cap)?)),
+        ["monoid", file] => Ok(Command::Monoid(read_monoid(file)?, DEFAULT_CAP)),
+        ["monoid", file, "--cap", cap] => Ok(Command::Monoid(read_monoid(file)?, parse_cap(cap)?)),
+        [cmd, ..] => Err(format!("unexpected arguments for '{}'", cmd)),
+    }
+}
+
+// Print the elements of a class on one line. Classes are already
+// sorted lexicographically, for consistent output.
+fn print_class(ec: &[Rig]) {
+    for elt in ec.iter() {
+        print!("{}, ", elt);
     }
+    println!();
 }
 
-impl Rig {
-    // The set of rigs can be converted to/from numbers 0..NUM_RIGS.
-    fn from(i: usize) -> Rig {
-        Rig {
-            i: i & 3,
-            a: (i >> 2) & 3,
-            b: (i >> 4) & 3,
-            ab: (i >> 6) & 3,
-            ba: (i >> 8) & 3,
-            aba: (i >> 10) & 3,
-            bab: (i >> 12) & 3,
+fn print_summary(quotient: &Quotient) {
+    let mut classes = quotient
+        .classes()
+        .iter()
+        .map(|x| x.len())
+        .collect::<Vec<usize>>();
+    classes.sort();
+    classes.reverse();
+    println!("Class sizes: {:?}", &classes);
+    println!("\nTotal number of elements: {}", classes.len());
+}
+
+// Say why a merge happened, in terms of other merges.
+fn describe(reason: &Reason) -> String {
+    match reason {
+        Reason::Square => "the right-hand side is the square of the left".to_string(),
+        Reason::Relation(k) => format!("extra relation {}", k + 1),
+        Reason::Congruence(m, Translation::Add(c)) => format!("add {} to both sides of [{}]", c, m),
+        Reason::Congruence(m, Translation::MulLeft(c)) => {
+            format!("multiply both sides of [{}] on the left by {}", m, c)
+        }
+        Reason::Congruence(m, Translation::MulRight(c)) => {
+            format!("multiply both sides of [{}] on the right by {}", m, c)
         }
     }
+}
+
+// Print a chain of equalities from x to y, followed by every merge it
+// relies on, each justified by earlier ones.
+fn print_explanation(quotient: &Quotient, x: &Rig, y: &Rig) {
+    let Some(steps) = quotient.explain(x, y) else {
+        println!("{} and {} are not equal", x, y);
+        return;
+    };
+    println!("{}", x);
+    for step in steps.iter() {
+        println!("  = {}    [{}]", step.to, step.merge);
+    }
 
-    fn to_int(&self) -> usize {
-        self.i
-            + (self.a << 2)
-            + (self.b << 4)
-            + (self.ab << 6)
-            + (self.ba << 8)
-            + (self.aba << 10)
-            + (self.bab << 12)
+    let merges = steps.iter().map(|step| step.merge).collect::<Vec<_>>();
+    let merges = quotient.union().dependencies(&merges);
+    if merges.is_empty() {
+        return;
     }
+    println!("\nwhere");
+    for m in merges {
+        let merge = quotient.union().merge(m);
+        println!("  [{}] {} = {}", m, merge.lhs, merge.rhs);
+        println!("      by {}", describe(&merge.reason));
+    }
+}
 
-    // Rules for addition and multiplication written out longhand
-    // because I'm silly.
-
-    fn add(&self, other: &Rig) -> Rig {
-        Rig {
-            i: self.i + other.i,
-            a: self.a + other.a,
-            b: self.b + other.b,
-            ab: self.ab + other.ab,
-            ba: self.ba + other.ba,
-            aba: self.aba + other.aba,
-            bab: self.bab + other.bab,
+// Report how the closure went, phase by phase.
+fn print_stats(stats: &ClosureStats) {
+    for phase in stats.phases.iter() {
+        eprintln!(
+            "{:>10}: {:>5} merges, {:>5} classes left, {:>8.3}s",
+            phase.name,
+            phase.merges,
+            phase.classes,
+            phase.elapsed.as_secs_f64()
+        );
+    }
+    eprintln!(
+        "Closure took {} passes, {} merges, {:.3}s",
+        stats.passes(),
+        stats.merges(),
+        stats.elapsed.as_secs_f64()
+    );
+}
+
+// Print the number of classes of each of Green's relations, the
+// J-order, and an egg-box diagram for each J-class. Elements are
+// written as the smallest member of their class.
+fn print_green(quotient: &Quotient) {
+    let green = Green::new(quotient);
+    let name = |x: usize| quotient.small_representative(x).to_string();
+    println!("L-classes: {}", green.l_classes.len());
+    println!("R-classes: {}", green.r_classes.len());
+    println!("H-classes: {}", green.h_classes.len());
+    println!("D-classes (= J-classes): {}", green.d_classes.len());
+
+    println!("\nJ-order, with each class followed by those directly below it:");
+    for i in 0..green.d_classes.len() {
+        let below = green
+            .covers
+            .iter()
+            .filter(|&&(upper, _)| upper == i)
+            .map(|&(_, lower)| format!("J{}", lower))
+            .collect::<Vec<_>>();
+        if below.is_empty() {
+            println!("  J{}", i);
+        } else {
+            println!("  J{} > {}", i, below.join(", "));
         }
-        .normalise()
     }
 
-    fn mul(&self, other: &Rig) -> Rig {
-        Rig {
-            i: self.i * other.i,
-
-            a: self.i * other.a + self.a * other.i + self.a * other.a,
-
-            b: self.i * other.b + self.b * other.i + self.b * other.b,
-
-            ab: self.i * other.ab
-                + self.ab * other.i
-                + self.ab * other.ab
-                + self.a * other.b
-                + self.a * other.ab
-                + self.a * other.bab
-                + self.ab * other.b
-                + self.ab * other.bab
-                + self.aba * other.b
-                + self.aba * other.ab
-                + self.aba * other.bab,
-
-            ba: self.i * other.ba
-                + self.ba * other.i
-                + self.ba * other.ba
-                + self.b * other.a
-                + self.b * other.ba
-                + self.b * other.aba
-                + self.ba * other.a
-                + self.ba * other.aba
-                + self.bab * other.a
-                + self.bab * other.ba
-                + self.bab * other.aba,
-
-            aba: self.i * other.aba
-                + self.aba * other.i
-                + self.aba * other.aba
-                + self.a * other.ba
-                + self.a * other.aba
-                + self.ab * other.a
-                + self.ab * other.ba
-                + self.ab * other.aba
-                + self.aba * other.a
-                + self.aba * other.ba,
-
-            bab: self.i * other.bab
-                + self.bab * other.i
-                + self.bab * other.bab
-                + self.b * other.ab
-                + self.b * other.bab
-                + self.ba * other.b
-                + self.ba * other.ab
-                + self.ba * other.bab
-                + self.bab * other.b
-                + self.bab * other.ab,
+    for d in 0..green.d_classes.len() {
+        let grid = green.egg_box(d);
+        let cells = grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|h| h.iter().map(|&x| name(x)).collect::<Vec<_>>().join(", "))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let widths = (0..cells[0].len())
+            .map(|j| cells.iter().map(|row| row[j].len()).max().unwrap())
+            .collect::<Vec<_>>();
+        let rule = widths
+            .iter()
+            .map(|&w| "-".repeat(w + 2))
+            .collect::<Vec<_>>()
+            .join("+");
+        println!(
+            "\nJ{}: {} R-classes by {} L-classes",
+            d,
+            grid.len(),
+            widths.len()
+        );
+        println!("+{}+", rule);
+        for row in cells.iter() {
+            let padded = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, &w)| format!(" {:w$} ", cell, w = w))
+                .collect::<Vec<_>>();
+            println!("|{}|", padded.join("|"));
+            println!("+{}+", rule);
         }
-        .normalise()
     }
+}
 
-    // Due to idempotency, x + x = (x + x) * (x + x) = x + x + x + x,
-    // so we can always reduce 4x to 2x.
-    //
-    // Moreover, 4x y = 4 xy = 2 xy = 2x y, so always normalising down
-    // doesn't change the "reachable" elements.
-    fn normalise(&self) -> Rig {
-        fn n(i: usize) -> usize {
-            if i >= 4 {
-                i % 2 + 2
-            } else {
-                i
-            }
+// Summarise the natural preorder: whether it's a partial order, and
+// if not, which elements are <= each other, and its extremes.
+fn print_order(quotient: &Quotient) {
+    let order = NaturalOrder::new(quotient);
+    let names = |block: &[usize]| {
+        block
+            .iter()
+            .map(|&x| quotient.small_representative(x).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if order.is_partial_order() {
+        println!("The natural preorder is a partial order.");
+    } else {
+        let merged = order.blocks.iter().filter(|b| b.len() > 1);
+        println!(
+            "The natural preorder isn't a partial order: {} elements fall into {} blocks.",
+            quotient.len(),
+            order.blocks.len()
+        );
+        println!("Blocks of more than one element:");
+        for block in merged {
+            println!("  {}", names(block));
         }
-        Rig {
-            i: n(self.i),
-            a: n(self.a),
-            b: n(self.b),
-            ab: n(self.ab),
-            ba: n(self.ba),
-            aba: n(self.aba),
-            bab: n(self.bab),
+    }
+    println!("Covering pairs: {}", order.covers.len());
+    for (title, blocks) in [("Minimal", order.minimal()), ("Maximal", order.maximal())] {
+        println!("{}:", title);
+        for i in blocks {
+            println!("  {}", names(&order.blocks[i]));
         }
     }
 }
 
-// Implement union-find ourselves, yet again.
-#[derive(Debug, Clone, Eq, PartialEq)]
-struct RigUnion {
-    ptrs: Vec<usize>,
+// Print a homomorphism telling x and y apart.
+fn print_separation(quotient: &Quotient, relations: &[(Rig, Rig)], x: &Rig, y: &Rig) {
+    if quotient.class_of(x) == quotient.class_of(y) {
+        println!("{} and {} are equal", x, y);
+        return;
+    }
+    match separate::separate(relations, x, y) {
+        Some(w) => {
+            println!("Homomorphism: {}", w.describe());
+            println!("{} -> {}", x, w.format(&w.eval(x)));
+            println!("{} -> {}", y, w.format(&w.eval(y)));
+        }
+        None => println!("No homomorphism into the matrix rigs tried separates them"),
+    }
 }
 
-impl RigUnion {
-    fn new() -> RigUnion {
-        // Initially, all pointers point to themselves.
-        RigUnion {
-            ptrs: (0..NUM_RIGS).collect::<Vec<_>>(),
+// Print a family of homomorphisms that tell every pair of classes
+// apart, which shows there are at least as many elements as classes.
+fn print_separating_family(quotient: &Quotient, relations: &[(Rig, Rig)]) {
+    match separate::separate_all(relations, quotient) {
+        Ok(family) => {
+            for w in family.iter() {
+                println!("{}", w.describe());
+            }
+            println!(
+                "\nThese {} homomorphisms separate all {} classes",
+                family.len(),
+                quotient.len()
+            );
+        }
+        Err(parts) => {
+            for part in parts.iter() {
+                let reps = part.iter().map(|&id| quotient.lex_representative(id));
+                let reps = reps.map(|r| r.to_string()).collect::<Vec<_>>();
+                println!("Not separated: {}", reps.join(", "));
+            }
+            println!("\n{} groups of classes couldn't be told apart", parts.len());
+            std::process::exit(1);
         }
     }
+}
 
-    fn union(&mut self, r1: &Rig, r2: &Rig) {
-        // Not efficient, just get it done.
-        let mut idx1 = r1.to_int();
-        let mut idx2 = r2.to_int();
+// Send some (possibly large) output to stdout, exiting on failure.
+fn write_stdout(f: impl FnOnce(&mut BufWriter<StdoutLock>) -> io::Result<()>) {
+    let mut out = BufWriter::new(io::stdout().lock());
+    if let Err(e) = f(&mut out).and_then(|_| out.flush()) {
+        eprintln!("rig: can't write output: {}", e);
+        std::process::exit(1);
+    }
+}
+
+// Explore the free idempotent rig on n generators, and report what
+// we found.
+fn print_free(n: usize, cap: usize) {
+    // There are at most `MAX_GENERATORS`, so at most 160 elements.
+    let monoid = Monoid::free_band(n, 160).unwrap();
+    println!(
+        "Free band with identity on {} generators: {} elements",
+        n,
+        monoid.len()
+    );
+    print_exploration(monoid, cap);
+}
 
-        // Dereference idx1's chain.
-        let mut tgt1 = idx1;
-        while self.ptrs[tgt1] != tgt1 {
-            assert!(self.ptrs[tgt1] < tgt1);
-            tgt1 = self.ptrs[tgt1];
+// Explore the free idempotent rig over a monoid, and report what we
+// found.
+fn print_exploration(monoid: Monoid, cap: usize) {
+    // The closure only finishes after finding all 4^m sums, so don't
+    // bother starting if the cap's smaller than that.
+    let m = monoid.len();
+    if 4usize.checked_pow(m as u32).is_none_or(|sums| sums > cap) {
+        eprintln!(
+            "rig: the closure needs all 4^{} sums over a {}-element monoid, more than \
+             the cap of {}",
+            m, m, cap
+        );
+        std::process::exit(1);
+    }
+    let exploration = explore(&MonoidRig::new(monoid), cap);
+    assert!(exploration.is_complete(), "stopped short of the cap");
+    let mut sizes = exploration
+        .classes()
+        .iter()
+        .map(|x| x.len())
+        .collect::<Vec<usize>>();
+    sizes.sort();
+    sizes.reverse();
+    println!("Class sizes: {:?}", &sizes);
+    println!("\nFree idempotent rig: {} elements", sizes.len());
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    if matches!(
+        args.first().map(String::as_str),
+        Some("help" | "-h" | "--help")
+    ) {
+        println!("{}", USAGE);
+        return;
+    }
+    let quiet = take_flag(&mut args, "--quiet");
+    let parsed = take_relations(&mut args).and_then(|relations| {
+        let command = parse_args(&args)?;
+        let two_generator = !matches!(command, Command::Free(..) | Command::Monoid(..));
+        if !relations.is_empty() && !two_generator {
+            return Err("relations only apply to the two-generator rig".to_string());
         }
-        // Dereference idx2's chain.
-        let mut tgt2 = idx2;
-        while self.ptrs[tgt2] != tgt2 {
-            assert!(self.ptrs[tgt2] < tgt2);
-            tgt2 = self.ptrs[tgt2];
+        Ok((relations, command))
+    });
+    let (exprs, command) = match parsed {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("rig: {}\n\n{}", msg, USAGE);
+            std::process::exit(2);
         }
-        // Use lowest index as target.
-        let tgt = tgt1.min(tgt2);
-
-        // Repoint idx1's chain to target.
-        while self.ptrs[idx1] != idx1 {
-            let tmp = self.ptrs[idx1];
-            self.ptrs[idx1] = tgt;
-            idx1 = tmp;
+    };
+
+    if let Command::Free(n, cap) = command {
+        print_free(n, cap);
+        return;
+    }
+    if let Command::Monoid(monoid, cap) = command {
+        let names = (0..monoid.len()).map(|x| monoid.name(x));
+        println!("Monoid: {}", names.collect::<Vec<_>>().join(" "));
+        let generators = monoid.generators().iter().map(|&x| monoid.name(x));
+        println!("Generators: {}", generators.collect::<Vec<_>>().join(" "));
+        print_exploration(monoid, cap);
+        return;
+    }
+    match command {
+        Command::Prover9(goal) => {
+            write_stdout(|out| provers::write_prover9(&exprs, &goal, out));
+            return;
         }
-        self.ptrs[idx1] = tgt;
-        // Repoint idx2's chain to target.
-        while self.ptrs[idx2] != idx2 {
-            let tmp = self.ptrs[idx2];
-            self.ptrs[idx2] = tgt;
-            idx2 = tmp;
+        Command::Tptp(goal) => {
+            write_stdout(|out| provers::write_tptp(&exprs, &goal, out));
+            return;
         }
-        self.ptrs[idx2] = tgt;
+        _ => (),
     }
 
-    // Break our data structure down into an array of equivalence
-    // classes.
-    fn get_classes(&mut self) -> Vec<Vec<Rig>> {
-        let mut sets: HashMap<usize, Vec<Rig>> = HashMap::new();
-        for i in 0..NUM_RIGS {
-            let rig = Rig::from(i);
-            // Normalise entry
-            self.union(&rig, &rig);
-
-            let tgt = self.ptrs[i];
-            sets.entry(tgt).or_insert(Vec::new()).push(rig);
-        }
-        // Sort by set size, then minimal value, to have consistent
-        // output.
-        let mut classes = sets
-            .into_values()
-            .map(|x| (x.len(), x.iter().min().unwrap().clone(), x))
-            .collect::<Vec<_>>();
-        classes.sort();
-        classes.into_iter().map(|x| x.2).collect::<Vec<_>>()
+    let relations = exprs
+        .iter()
+        .map(|relation| relation.to_rigs().unwrap())
+        .collect::<Vec<_>>();
+
+    let quotient = compute_presented(&relations);
+    if !quiet {
+        print_stats(quotient.stats());
     }
-}
 
-fn main() {
-    let mut equiv_classes = RigUnion::new();
-
-    // First of all, generate the equivalence classes over rigs and
-    // their squares.
-    for i in 0..NUM_RIGS {
-        // Identify all rigs with their squares.
-        let rig = Rig::from(i);
-        let rigrig = rig.mul(&rig);
-        equiv_classes.union(&rig, &rigrig);
+    // Check the result is really an idempotent rig before reporting
+    // anything about it.
+    if let Err(e) = quotient.verify() {
+        eprintln!("rig: the computed quotient isn't an idempotent rig: {}", e);
+        std::process::exit(1);
     }
 
-    // And then identify all the results of addition and
-    // multiplication - that is, if A and B are equivalence classes,
-    // ensure A_i * B_j are all in the same class, and A_i + B_j are
-    // also in the same class.
-
-    // I'm not absolutely totally sure one pass does here (I think it
-    // does, since union-find should do its magic), so iterate until
-    // fixed point, just in case.
-    let mut old = RigUnion::new();
-    while equiv_classes != old {
-        old = equiv_classes.clone();
-
-        // Identify different variants over addition
-        for i in 0..NUM_RIGS {
-            // Slow enough that you want to run in release mode, and
-            // displaying lots of numbers makes it feel like
-            // progress. This is inefficient code!
-            eprintln!("a{}", i);
-            for j in 0..NUM_RIGS {
-                let tgti = equiv_classes.ptrs[i];
-                let tgtj = equiv_classes.ptrs[j];
-
-                if tgti != i || tgtj != j {
-                    let rigi = Rig::from(i);
-                    let rigj = Rig::from(j);
-                    let rigij = rigi.add(&rigj);
-
-                    let trigi = Rig::from(tgti);
-                    let trigj = Rig::from(tgtj);
-                    let trigij = trigi.add(&trigj);
-
-                    equiv_classes.union(&rigij, &trigij);
-                }
+    match command {
+        Command::Classes => {
+            for ec in quotient.classes().iter() {
+                print_class(ec);
             }
         }
 
-        // Identify different variants over multiplication
-        for i in 0..NUM_RIGS {
-            eprintln!("m{}", i);
-            for j in 0..NUM_RIGS {
-                let tgti = equiv_classes.ptrs[i];
-                let tgtj = equiv_classes.ptrs[j];
-
-                if tgti != i || tgtj != j {
-                    let rigi = Rig::from(i);
-                    let rigj = Rig::from(j);
-                    let rigij = rigi.mul(&rigj);
+        Command::Representatives(Order::Small) => {
+            // Print "smallest" element of each equivalence class.
+            for id in 0..quotient.len() {
+                println!("{}", quotient.small_representative(id));
+            }
+        }
 
-                    let trigi = Rig::from(tgti);
-                    let trigj = Rig::from(tgtj);
-                    let trigij = trigi.mul(&trigj);
+        Command::Representatives(Order::Lex) => {
+            // Print lexicographically-first element in each
+            // equivalence class.
+            for id in 0..quotient.len() {
+                println!("{}", quotient.lex_representative(id));
+            }
+        }
 
-                    equiv_classes.union(&rigij, &trigij);
-                }
+        Command::Summary => print_summary(&quotient),
+
+        Command::Lookup(rig, members) => {
+            let info = quotient.lookup(&rig);
+            println!("Element:              {}", rig);
+            println!("Class id:             {}", info.id);
+            println!("Class size:           {}", info.size);
+            println!("Lex representative:   {}", info.lex);
+            println!("Small representative: {}", info.small);
+            if members {
+                println!();
+                print_class(quotient.class(info.id));
             }
         }
-    }
 
-    fn cost(r: &Rig) -> (usize, usize) {
-        let ci = if r.i != 0 { 1 } else { 0 };
-        let ca = if r.a != 0 { 1 } else { 0 };
-        let cb = if r.b != 0 { 1 } else { 0 };
-        let cab = if r.ab != 0 { 1 } else { 0 };
-        let cba = if r.ba != 0 { 1 } else { 0 };
-        let caba = if r.aba != 0 { 1 } else { 0 };
-        let cbab = if r.bab != 0 { 1 } else { 0 };
-
-        (
-            ci + ca + cb + cab + cba + caba + cbab,
-            r.i + r.a + r.b + r.ab + r.ba + r.aba + r.bab,
-        )
-    }
+        Command::Explain(x, y) => print_explanation(&quotient, &x, &y),
 
-    const PRINT_SMALLEST: bool = false;
-    const PRINT_LEXI: bool = false;
-    const PRINT_ALL: bool = true;
-    
-    // Print "smallest" element of each equivalence class.
-    if PRINT_SMALLEST {
-        for ec in equiv_classes.get_classes().iter() {
-            println!("{}", ec.iter().map(|x| (cost(x), x)).min().unwrap().1);
+        Command::Lean(goal) => {
+            let goals = goal.into_iter().collect::<Vec<_>>();
+            write_stdout(|out| lean::write_lean(quotient.union(), &relations, &goals, out))
         }
-    }
 
-    // Print lexicographically-first element in each equivalence
-    // class, according to Rust's auto-generated comparison operator.
-    if PRINT_LEXI {
-        for ec in equiv_classes.get_classes().iter() {
-            println!("{}", ec.iter().min().unwrap());
-        }
-    }
-    
-    if PRINT_ALL {
-        for ec in equiv_classes.get_classes().iter() {
-	    // Sort each set lexicographically, for consistent output.
-	    let mut tmp: Vec<_> = ec.clone();
-	    tmp.sort();
-	    for elt in tmp.iter() {
-		print!("{}, ", elt);
-	    }
-	    println!("");
+        Command::Separate(x, y) => print_separation(&quotient, &relations, &x, &y),
+
+        Command::SeparateAll => print_separating_family(&quotient, &relations),
+
+        Command::Truncation(bound) => match check_truncation(&quotient, &relations, bound) {
+            Ok(t) => {
+                println!("Sums with coefficients 0..{}: {}", t.bound - 1, t.elements);
+                println!("Merges: {}", t.merges);
+                println!("Classes: {}", t.classes);
+                println!("Shown equal to a sum with coefficients 0..3: {}", t.reduced);
+                println!(
+                    "\nThe sums with coefficients 0..3 fall into the same {} classes as with reduction",
+                    quotient.len()
+                );
+            }
+            Err(e) => {
+                println!("The closure without reduction doesn't match: {}", e);
+                println!("(A bigger bound can only merge more, so may still match.)");
+                std::process::exit(1);
+            }
+        },
+
+        Command::TableCsv(op) => write_stdout(|out| export::write_csv(&quotient, op, out)),
+
+        Command::TableBinary => write_stdout(|out| export::write_binary(&quotient, out)),
+
+        Command::Json => write_stdout(|out| export::write_json(&quotient, out)),
+
+        Command::Gap => write_stdout(|out| export::write_gap(&quotient, out)),
+
+        Command::Sage => write_stdout(|out| export::write_sage(&quotient, out)),
+
+        Command::Mace4 => write_stdout(|out| provers::write_mace4(&quotient, out)),
+
+        Command::Green => print_green(&quotient),
+
+        Command::Order(false) => print_order(&quotient),
+
+        Command::Order(true) => {
+            write_stdout(|out| NaturalOrder::new(&quotient).write_dot(&quotient, out))
         }
-    }
 
-    if !PRINT_SMALLEST && !PRINT_LEXI && !PRINT_ALL {
-	// Just print a summary.
-	let mut classes = equiv_classes
-            .get_classes()
-            .iter()
-            .map(|x| x.len())
-            .collect::<Vec<usize>>();
-	classes.sort();
-	classes.reverse();
-	println!("Class sizes: {:?}", &classes);
-	println!("\nTotal number of elements: {}", classes.len());
+        Command::Free(..) | Command::Monoid(..) | Command::Prover9(_) | Command::Tptp(_) => {
+            unreachable!()
+        }
     }
 }
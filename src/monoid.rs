@@ -0,0 +1,251 @@
+//
+// Finite monoids, given by their multiplication tables, to act as the
+// multiplicative part of a rig: the rig's elements are then sums of
+// monoid elements.
+//
+// The two-generator rig uses the free band with identity on {a, b},
+// the seven words 1, a, b, ab, ba, aba, bab. `Monoid::free_band`
+// builds the same thing for any number of generators, and any other
+// monoid can be read from a text file holding its Cayley table:
+//
+//     # Comments run to the end of the line.
+//         e  f
+//     e   e  f
+//     f   e  f
+//
+// The first line names the elements, and then there's a row per
+// element, starting with its name, giving its products with the
+// elements in the order of the first line. If no element is an
+// identity, one called "1" is added, so semigroups (like the
+// rectangular band above) are fine too.
+//
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::band;
+
+#[derive(Clone, Debug)]
+pub struct Monoid {
+    // How to print each element.
+    names: Vec<String>,
+    // table[x][y] is the index of x * y.
+    table: Vec<Vec<usize>>,
+    identity: usize,
+    // Elements that generate the whole monoid.
+    generators: Vec<usize>,
+}
+
+impl Monoid {
+    // Number of elements.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, x: usize) -> &str {
+        &self.names[x]
+    }
+
+    pub fn mul(&self, x: usize, y: usize) -> usize {
+        self.table[x][y]
+    }
+
+    pub fn identity(&self) -> usize {
+        self.identity
+    }
+
+    pub fn generators(&self) -> &[usize] {
+        &self.generators
+    }
+
+    // Build a monoid from its multiplication table, checking that it
+    // is associative. If no element is an identity, one called "1" is
+    // adjoined.
+    pub fn from_table(
+        mut names: Vec<String>,
+        mut table: Vec<Vec<usize>>,
+    ) -> Result<Monoid, String> {
+        let n = names.len();
+        if table.len() != n || table.iter().any(|row| row.len() != n) {
+            return Err(format!("table should be {} by {}", n, n));
+        }
+        if table.iter().flatten().any(|&xy| xy >= n) {
+            return Err("table refers to a nonexistent element".to_string());
+        }
+        for x in 0..n {
+            for y in 0..n {
+                for z in 0..n {
+                    if table[table[x][y]][z] != table[x][table[y][z]] {
+                        return Err(format!(
+                            "not associative: ({} {}) {} != {} ({} {})",
+                            names[x], names[y], names[z], names[x], names[y], names[z]
+                        ));
+                    }
+                }
+            }
+        }
+
+        let is_identity = |e: usize| (0..n).all(|x| table[e][x] == x && table[x][e] == x);
+        let identity = match (0..n).find(|&e| is_identity(e)) {
+            Some(e) => e,
+            None => {
+                if names.iter().any(|name| name == "1") {
+                    return Err("no identity, and \"1\" is already taken".to_string());
+                }
+                for (x, row) in table.iter_mut().enumerate() {
+                    row.push(x);
+                }
+                table.push((0..=n).collect());
+                names.push("1".to_string());
+                n
+            }
+        };
+
+        // Greedily pick generators: any element not generated by the
+        // ones so far.
+        let mut generators = Vec::new();
+        let mut generated = vec![false; names.len()];
+        generated[identity] = true;
+        for x in 0..names.len() {
+            if generated[x] {
+                continue;
+            }
+            generators.push(x);
+            let mut stack = (0..names.len())
+                .filter(|&y| generated[y])
+                .collect::<Vec<_>>();
+            while let Some(y) = stack.pop() {
+                for &g in generators.iter() {
+                    let yg = table[y][g];
+                    if !generated[yg] {
+                        generated[yg] = true;
+                        stack.push(yg);
+                    }
+                }
+            }
+        }
+
+        Ok(Monoid {
+            names,
+            table,
+            identity,
+            generators,
+        })
+    }
+
+    // The free band (idempotent semigroup) on n generators, called a,
+    // b, c, ..., with an identity adjoined. It's finite, but grows
+    // very quickly (2, 7, 160, 332381, ... elements), so give up if
+    // there are more than `max_size` elements.
+    //
+    // Each element is named by its shortlex-least word, so the
+    // two-generator case gives 1, a, b, ab, ba, aba, bab, in that
+    // order.
+    pub fn free_band(n: usize, max_size: usize) -> Option<Monoid> {
+        assert!(n <= 26, "generators are named by single letters");
+
+        // Breadth-first search over words, appending one letter at a
+        // time. Shortlex-least words have shortlex-least prefixes, so
+        // the first word found for each element is its name.
+        let mut words: Vec<Vec<u8>> = vec![Vec::new()];
+        let mut index = HashMap::new();
+        index.insert(Vec::new(), 0);
+        let mut next = 0;
+        while next < words.len() {
+            for letter in 0..n as u8 {
+                let mut word = words[next].clone();
+                word.push(letter);
+                if let Entry::Vacant(e) = index.entry(band::normal_form(&word)) {
+                    if words.len() == max_size {
+                        return None;
+                    }
+                    e.insert(words.len());
+                    words.push(word);
+                }
+            }
+            next += 1;
+        }
+
+        let table = words
+            .iter()
+            .map(|x| {
+                words
+                    .iter()
+                    .map(|y| index[&band::product(x, y)])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let names = words
+            .iter()
+            .map(|w| {
+                if w.is_empty() {
+                    "1".to_string()
+                } else {
+                    w.iter().map(|&l| (b'a' + l) as char).collect()
+                }
+            })
+            .collect();
+
+        Some(Monoid {
+            names,
+            table,
+            identity: 0,
+            generators: (1..=n).collect(),
+        })
+    }
+}
+
+// Read a Cayley table in the format described at the top of the file.
+impl FromStr for Monoid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Monoid, String> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split('#').next().unwrap()))
+            .map(|(i, line)| (i, line.split_whitespace().collect::<Vec<_>>()))
+            .filter(|(_, words)| !words.is_empty());
+
+        let (_, header) = lines.next().ok_or("no elements")?;
+        let names = header.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let index = |line: usize, name: &str| {
+            names
+                .iter()
+                .position(|x| x == name)
+                .ok_or_else(|| format!("line {}: unknown element '{}'", line, name))
+        };
+
+        let mut table = vec![None; names.len()];
+        for (line, words) in lines {
+            if words.len() != names.len() + 1 {
+                return Err(format!(
+                    "line {}: expected {} entries",
+                    line,
+                    names.len() + 1
+                ));
+            }
+            let x = index(line, words[0])?;
+            if table[x].is_some() {
+                return Err(format!("line {}: second row for '{}'", line, words[0]));
+            }
+            let row = words[1..]
+                .iter()
+                .map(|name| index(line, name))
+                .collect::<Result<Vec<_>, _>>()?;
+            table[x] = Some(row);
+        }
+        let table = table
+            .into_iter()
+            .zip(names.iter())
+            .map(|(row, name)| row.ok_or_else(|| format!("no row for '{}'", name)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Monoid::from_table(names, table)
+    }
+}
@@ -0,0 +1,142 @@
+//
+// The natural preorder on the quotient: x <= y iff y = x + z for some
+// z.
+//
+// It's reflexive (z = 0) and transitive (add the two zs), but need
+// not be antisymmetric. In fact it isn't here: 3 = 2 + 1, but also 2 =
+// 3 + 3, as 6 = 4 = 2. So we group elements that are each <= the
+// other into blocks, which the preorder turns into a partial order,
+// and find the covering relation between blocks, i.e. the edges of
+// the Hasse diagram.
+//
+
+use std::io::{self, Write};
+
+use crate::quotient::Quotient;
+
+#[derive(Clone, Debug)]
+pub struct NaturalOrder {
+    n: usize,
+    // Whether x <= y, indexed by `x * n + y`.
+    le: Vec<bool>,
+    // The elements that are each <= the other, each block in
+    // increasing order, and ordered by their smallest element.
+    pub blocks: Vec<Vec<usize>>,
+    block_of: Vec<usize>,
+    // The covering relation on blocks: (i, j) means block i is
+    // directly below block j.
+    pub covers: Vec<(usize, usize)>,
+}
+
+impl NaturalOrder {
+    pub fn new(quotient: &Quotient) -> NaturalOrder {
+        let n = quotient.len();
+        let mut le = vec![false; n * n];
+        for x in 0..n {
+            for z in 0..n {
+                le[x * n + quotient.add(x, z)] = true;
+            }
+        }
+
+        let mut blocks: Vec<Vec<usize>> = Vec::new();
+        let mut block_of = vec![0; n];
+        for y in 0..n {
+            match blocks
+                .iter()
+                .position(|b| le[b[0] * n + y] && le[y * n + b[0]])
+            {
+                Some(i) => {
+                    blocks[i].push(y);
+                    block_of[y] = i;
+                }
+                None => {
+                    block_of[y] = blocks.len();
+                    blocks.push(vec![y]);
+                }
+            }
+        }
+
+        // i is below j when the blocks differ and i's elements are <=
+        // j's, and i is covered by j when nothing is strictly between
+        // them.
+        let m = blocks.len();
+        let below = |i: usize, j: usize| i != j && le[blocks[i][0] * n + blocks[j][0]];
+        let mut covers = Vec::new();
+        for i in 0..m {
+            for j in 0..m {
+                if below(i, j) && !(0..m).any(|k| below(i, k) && below(k, j)) {
+                    covers.push((i, j));
+                }
+            }
+        }
+
+        NaturalOrder {
+            n,
+            le,
+            blocks,
+            block_of,
+            covers,
+        }
+    }
+
+    pub fn le(&self, x: usize, y: usize) -> bool {
+        self.le[x * self.n + y]
+    }
+
+    // The block containing an element.
+    pub fn block(&self, x: usize) -> usize {
+        self.block_of[x]
+    }
+
+    // A partial order is a preorder where every block is a single
+    // element.
+    pub fn is_partial_order(&self) -> bool {
+        self.blocks.len() == self.n
+    }
+
+    // Blocks with nothing below them, and with nothing above them.
+    pub fn minimal(&self) -> Vec<usize> {
+        (0..self.blocks.len())
+            .filter(|&i| !self.covers.iter().any(|&(_, upper)| upper == i))
+            .collect()
+    }
+
+    pub fn maximal(&self) -> Vec<usize> {
+        (0..self.blocks.len())
+            .filter(|&i| !self.covers.iter().any(|&(lower, _)| lower == i))
+            .collect()
+    }
+
+    // Write the Hasse diagram as a GraphViz digraph, with edges
+    // pointing up from each block to those covering it, and drawn
+    // bottom to top. Each node is labelled with the smallest elements
+    // of the classes in its block.
+    pub fn write_dot(&self, quotient: &Quotient, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "// The natural preorder: x <= y iff y = x + z for some z."
+        )?;
+        if self.is_partial_order() {
+            writeln!(out, "// It's a partial order.")?;
+        } else {
+            writeln!(
+                out,
+                "// It isn't a partial order, so nodes are blocks of elements <= each other."
+            )?;
+        }
+        writeln!(out, "digraph natural_order {{")?;
+        writeln!(out, "  rankdir = BT;")?;
+        writeln!(out, "  node [shape = box];")?;
+        for (i, block) in self.blocks.iter().enumerate() {
+            let names = block
+                .iter()
+                .map(|&x| quotient.small_representative(x).to_string())
+                .collect::<Vec<_>>();
+            writeln!(out, "  n{} [label = \"{}\"];", i, names.join("\\n"))?;
+        }
+        for &(lower, upper) in self.covers.iter() {
+            writeln!(out, "  n{} -> n{};", lower, upper)?;
+        }
+        writeln!(out, "}}")
+    }
+}
@@ -0,0 +1,257 @@
+//
+// Parser for rig expressions, so that elements can be typed in rather
+// than built up in code.
+//
+// The grammar accepts sums and products of generators (single
+// lower-case letters), natural numbers and parenthesised
+// subexpressions. Multiplication may be written with `*` or by
+// juxtaposition, so that anything printed by `Rig`'s `Display`
+// implementation (like `2a + ab + bab`) reads back in, as does
+// `(a + b)(a + b)ab`.
+//
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::rig::{Rig, A, B};
+use crate::semiring::Semiring;
+
+// An unevaluated rig expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expr {
+    // A natural number n, meaning 1 + 1 + ... + 1 (n times).
+    Num(usize),
+    // A generator.
+    Var(char),
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+// An equation between two expressions, like "a + b = 1", to impose
+// as an extra relation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Relation {
+    pub lhs: Expr,
+    pub rhs: Expr,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    // Character offset of the problem in the input.
+    pub pos: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.msg, self.pos)
+    }
+}
+
+impl Error for ParseError {}
+
+impl Expr {
+    // Parse an expression whose generators are drawn from the given
+    // letters.
+    pub fn parse_with(s: &str, generators: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+            generators: generators.to_string(),
+        };
+        let e = parser.sum()?;
+        if parser.peek().is_some() {
+            return parser.error("unexpected character");
+        }
+        Ok(e)
+    }
+
+    // Evaluate the expression in any semiring, given the images of
+    // the generators a and b. Fails if the expression uses any other
+    // generators.
+    pub fn eval<S: Semiring>(&self, a: &S, b: &S) -> Option<S> {
+        Some(match self {
+            Expr::Num(n) => a.nat(*n),
+            Expr::Var('a') => a.clone(),
+            Expr::Var('b') => b.clone(),
+            Expr::Var(_) => return None,
+            Expr::Add(x, y) => x.eval(a, b)?.add(&y.eval(a, b)?),
+            Expr::Mul(x, y) => x.eval(a, b)?.mul(&y.eval(a, b)?),
+        })
+    }
+
+    // Reduce the expression to a normal-form element of the
+    // two-generator rig, using the rig's own operations.
+    pub fn to_rig(&self) -> Option<Rig> {
+        self.eval(&A, &B)
+    }
+}
+
+impl Relation {
+    // Parse "lhs = rhs", where the expressions' generators are drawn
+    // from the given letters.
+    pub fn parse_with(s: &str, generators: &str) -> Result<Relation, ParseError> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+            generators: generators.to_string(),
+        };
+        let lhs = parser.sum()?;
+        if parser.peek() != Some('=') {
+            return parser.error("expected '='");
+        }
+        parser.pos += 1;
+        let rhs = parser.sum()?;
+        if parser.peek().is_some() {
+            return parser.error("unexpected character");
+        }
+        Ok(Relation { lhs, rhs })
+    }
+
+    // Both sides as normal-form elements of the two-generator rig.
+    pub fn to_rigs(&self) -> Option<(Rig, Rig)> {
+        Some((self.lhs.to_rig()?, self.rhs.to_rig()?))
+    }
+}
+
+// Simple recursive descent parser, one character at a time.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    // The letters allowed as generators.
+    generators: String,
+}
+
+impl Parser {
+    fn error<T>(&self, msg: &str) -> Result<T, ParseError> {
+        Err(ParseError {
+            pos: self.pos,
+            msg: msg.to_string(),
+        })
+    }
+
+    // Next non-whitespace character, without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    // sum := product ('+' product)*
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut acc = self.product()?;
+        while self.peek() == Some('+') {
+            self.pos += 1;
+            acc = Expr::Add(Box::new(acc), Box::new(self.product()?));
+        }
+        Ok(acc)
+    }
+
+    // product := factor ('*'? factor)*
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut acc = self.factor()?;
+        loop {
+            match self.peek() {
+                Some('*') => self.pos += 1,
+                Some(c) if c == '(' || c.is_ascii_digit() || c.is_ascii_lowercase() => (),
+                _ => return Ok(acc),
+            }
+            acc = Expr::Mul(Box::new(acc), Box::new(self.factor()?));
+        }
+    }
+
+    // factor := number | generator | '(' sum ')'
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.pos < self.chars.len() && self.chars[self.pos].is_ascii_digit() {
+                    self.pos += 1;
+                }
+                let digits = self.chars[start..self.pos].iter().collect::<String>();
+                match digits.parse() {
+                    Ok(n) => Ok(Expr::Num(n)),
+                    Err(_) => {
+                        self.pos = start;
+                        self.error("number too large")
+                    }
+                }
+            }
+            Some(c) if self.generators.contains(c) => {
+                self.pos += 1;
+                Ok(Expr::Var(c))
+            }
+            Some(c) if c.is_ascii_lowercase() => self.error(&format!("unknown generator '{}'", c)),
+            Some('(') => {
+                self.pos += 1;
+                let e = self.sum()?;
+                if self.peek() != Some(')') {
+                    return self.error("expected ')'");
+                }
+                self.pos += 1;
+                Ok(e)
+            }
+            Some(_) => self.error("expected a number, generator or '('"),
+            None => self.error("unexpected end of input"),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    // Any lower-case letter may be used as a generator.
+    fn from_str(s: &str) -> Result<Expr, ParseError> {
+        Expr::parse_with(s, "abcdefghijklmnopqrstuvwxyz")
+    }
+}
+
+impl FromStr for Relation {
+    type Err = ParseError;
+
+    // Any lower-case letter may be used as a generator.
+    fn from_str(s: &str) -> Result<Relation, ParseError> {
+        Relation::parse_with(s, "abcdefghijklmnopqrstuvwxyz")
+    }
+}
+
+impl FromStr for Rig {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Rig, ParseError> {
+        Ok(Expr::parse_with(s, "ab")?.to_rig().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rig::NUM_RIGS;
+
+    fn error(s: &str) -> (usize, String) {
+        let e = s.parse::<Rig>().unwrap_err();
+        (e.pos, e.msg)
+    }
+
+    // Everything `Display` prints should read back in as itself.
+    #[test]
+    fn round_trip() {
+        for i in 0..NUM_RIGS {
+            let r = Rig::from(i);
+            assert_eq!(r.to_string().parse::<Rig>(), Ok(r.clone()), "{}", r);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("(a"), (2, "expected ')'".to_string()));
+        assert_eq!(error("a+"), (2, "unexpected end of input".to_string()));
+        assert_eq!(error("c"), (0, "unknown generator 'c'".to_string()));
+        assert_eq!(
+            error("a + 99999999999999999999999"),
+            (4, "number too large".to_string())
+        );
+    }
+}
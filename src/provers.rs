@@ -0,0 +1,197 @@
+//
+// Writing problems out for automated reasoners, to cross-check the
+// closure with something independent.
+//
+// Prover9 and TPTP problems state the idempotent rig axioms, any
+// extra relations, and an identity in a and b to prove. Since the rig
+// is free (or finitely presented), an identity holds in it exactly
+// when it follows from the axioms with a and b as constants. The
+// provers can also be pointed at an identity that doesn't hold, in
+// which case Mace4 should find a counter-model, e.g. one of the small
+// matrix rigs from `separate.rs`.
+//
+// Going the other way, the Mace4 model is the quotient's tables in
+// Mace4's interpretation format, so that e.g. `clausetester` can
+// check the axioms hold in it.
+//
+
+use std::io::{self, Write};
+
+use crate::parse::{Expr, Relation};
+use crate::quotient::{Op, Quotient};
+
+// The axioms, as (name, Prover9 formula, TPTP formula). Prover9 takes
+// variables to be anything starting u to z, TPTP anything upper case.
+// That rules out calling 0 "zero" in Prover9, where it'd be a
+// variable, so it's "e0" there.
+const AXIOMS: [(&str, &str, &str); 11] = [
+    (
+        "add_assoc",
+        "(x + y) + z = x + (y + z)",
+        "![X, Y, Z] : plus(plus(X, Y), Z) = plus(X, plus(Y, Z))",
+    ),
+    (
+        "add_comm",
+        "x + y = y + x",
+        "![X, Y] : plus(X, Y) = plus(Y, X)",
+    ),
+    ("add_zero", "x + e0 = x", "![X] : plus(X, zero) = X"),
+    (
+        "mul_assoc",
+        "(x * y) * z = x * (y * z)",
+        "![X, Y, Z] : times(times(X, Y), Z) = times(X, times(Y, Z))",
+    ),
+    ("one_mul", "one * x = x", "![X] : times(one, X) = X"),
+    ("mul_one", "x * one = x", "![X] : times(X, one) = X"),
+    ("zero_mul", "e0 * x = e0", "![X] : times(zero, X) = zero"),
+    ("mul_zero", "x * e0 = e0", "![X] : times(X, zero) = zero"),
+    (
+        "left_distrib",
+        "x * (y + z) = (x * y) + (x * z)",
+        "![X, Y, Z] : times(X, plus(Y, Z)) = plus(times(X, Y), times(X, Z))",
+    ),
+    (
+        "right_distrib",
+        "(x + y) * z = (x * z) + (y * z)",
+        "![X, Y, Z] : times(plus(X, Y), Z) = plus(times(X, Z), times(Y, Z))",
+    ),
+    ("mul_self", "x * x = x", "![X] : times(X, X) = X"),
+];
+
+#[derive(Clone, Copy)]
+enum Syntax {
+    Prover9,
+    Tptp,
+}
+
+// The number n as an expression in 0, 1, + and *. Up to 3 it's 1 + 1
+// + ... + 1, and beyond that it's built by doubling, as 2 * (n / 2),
+// plus 1 if n is odd, so it stays small even for big numbers.
+fn numeral(n: usize) -> Expr {
+    let sum = |x: Expr, y: Expr| Expr::Add(Box::new(x), Box::new(y));
+    match n {
+        0 | 1 => Expr::Num(n),
+        2 | 3 => sum(numeral(n - 1), Expr::Num(1)),
+        _ => {
+            let double = Expr::Mul(Box::new(numeral(2)), Box::new(numeral(n / 2)));
+            if n % 2 == 1 {
+                sum(double, Expr::Num(1))
+            } else {
+                double
+            }
+        }
+    }
+}
+
+// Write an expression as a term. In Prover9, + and * are infix but
+// not associative, so every sum and product is bracketed, apart from
+// the outermost.
+fn term(expr: &Expr, syntax: Syntax) -> String {
+    fn bracketed(expr: &Expr, syntax: Syntax) -> String {
+        match (expr, syntax) {
+            (Expr::Add(..) | Expr::Mul(..), Syntax::Prover9) => format!("({})", term(expr, syntax)),
+            (Expr::Num(n), Syntax::Prover9) if *n > 1 => format!("({})", term(expr, syntax)),
+            _ => term(expr, syntax),
+        }
+    }
+    let binary = |op: &str, name: &str, x: &Expr, y: &Expr| match syntax {
+        Syntax::Prover9 => format!("{} {} {}", bracketed(x, syntax), op, bracketed(y, syntax)),
+        Syntax::Tptp => format!("{}({}, {})", name, term(x, syntax), term(y, syntax)),
+    };
+    match expr {
+        Expr::Num(0) => match syntax {
+            Syntax::Prover9 => "e0".to_string(),
+            Syntax::Tptp => "zero".to_string(),
+        },
+        Expr::Num(1) => "one".to_string(),
+        Expr::Num(n) => term(&numeral(*n), syntax),
+        Expr::Var(c) => c.to_string(),
+        Expr::Add(x, y) => binary("+", "plus", x, y),
+        Expr::Mul(x, y) => binary("*", "times", x, y),
+    }
+}
+
+fn equation(relation: &Relation, syntax: Syntax) -> String {
+    format!(
+        "{} = {}",
+        term(&relation.lhs, syntax),
+        term(&relation.rhs, syntax)
+    )
+}
+
+// Write a Prover9 input file: the axioms and relations as assumptions,
+// and the goal to prove.
+pub fn write_prover9(
+    relations: &[Relation],
+    goal: &Relation,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "% Idempotent rigs, with generators a and b.")?;
+    writeln!(out)?;
+    writeln!(out, "formulas(assumptions).")?;
+    for (name, formula, _) in AXIOMS.iter() {
+        writeln!(out, "  {}.  % {}", formula, name)?;
+    }
+    for (k, relation) in relations.iter().enumerate() {
+        writeln!(
+            out,
+            "  {}.  % relation {}",
+            equation(relation, Syntax::Prover9),
+            k + 1
+        )?;
+    }
+    writeln!(out, "end_of_list.")?;
+    writeln!(out)?;
+    writeln!(out, "formulas(goals).")?;
+    writeln!(out, "  {}.", equation(goal, Syntax::Prover9))?;
+    writeln!(out, "end_of_list.")
+}
+
+// Write the same problem in TPTP's first-order form.
+pub fn write_tptp(relations: &[Relation], goal: &Relation, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "% Idempotent rigs, with generators a and b.")?;
+    writeln!(out)?;
+    for (name, _, formula) in AXIOMS.iter() {
+        writeln!(out, "fof({}, axiom, {}).", name, formula)?;
+    }
+    for (k, relation) in relations.iter().enumerate() {
+        writeln!(
+            out,
+            "fof(relation_{}, axiom, {}).",
+            k + 1,
+            equation(relation, Syntax::Tptp)
+        )?;
+    }
+    writeln!(
+        out,
+        "fof(goal, conjecture, {}).",
+        equation(goal, Syntax::Tptp)
+    )
+}
+
+// Write the quotient as a Mace4 interpretation, on the class ids, with
+// the same symbols as `write_prover9`. Binary operations are listed
+// row by row, so the entry for x + y is at x * n + y.
+pub fn write_mace4(quotient: &Quotient, out: &mut impl Write) -> io::Result<()> {
+    let id = |rig: &str| quotient.class_of(&rig.parse().unwrap());
+    let n = quotient.len();
+    writeln!(out, "interpretation( {}, [number = 1, seconds = 0], [", n)?;
+    for (name, rig) in [("e0", "0"), ("one", "1"), ("a", "a"), ("b", "b")] {
+        writeln!(out, "  function({}, [{}]),", name, id(rig))?;
+    }
+    for op in [Op::Add, Op::Mul] {
+        let symbol = match op {
+            Op::Add => "+",
+            Op::Mul => "*",
+        };
+        writeln!(out, "  function({}(_,_), [", symbol)?;
+        for x in 0..n {
+            let row = (0..n).map(|y| quotient.op(op, x, y).to_string());
+            let sep = if x + 1 < n { "," } else { "" };
+            writeln!(out, "    {}{}", row.collect::<Vec<_>>().join(","), sep)?;
+        }
+        let sep = if matches!(op, Op::Add) { "," } else { "" };
+        writeln!(out, "  ]){}", sep)?;
+    }
+    writeln!(out, "]).")
+}
@@ -0,0 +1,325 @@
+//
+// The result of the closure: the free idempotent rig on two
+// generators, as equivalence classes of normal-form elements.
+//
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Mul};
+
+use crate::closure::ClosureStats;
+use crate::rig::{cost, Rig, A, B, NUM_RIGS, ONE, ZERO};
+use crate::union::{RigUnion, Step};
+
+// What a lookup reports about the class of an element.
+#[derive(Clone, Debug)]
+pub struct ClassInfo<'a> {
+    // Index of the class, in the order of `Quotient::classes`.
+    pub id: usize,
+    pub size: usize,
+    // Lexicographically-first element of the class.
+    pub lex: &'a Rig,
+    // Element of the class with the smallest `cost`.
+    pub small: &'a Rig,
+}
+
+// The two operations of the rig.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Op {
+    Add,
+    Mul,
+}
+
+// An element of the quotient rig, for doing algebra directly: `+` and
+// `*` look up the quotient's tables. It's just a class id plus a
+// reference to the quotient, so it's cheap to copy around.
+#[derive(Clone, Copy)]
+pub struct Element<'a> {
+    quotient: &'a Quotient,
+    id: usize,
+}
+
+impl<'a> Element<'a> {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn quotient(&self) -> &'a Quotient {
+        self.quotient
+    }
+
+    // The lexicographically-first member of the class.
+    pub fn to_rig(&self) -> Rig {
+        self.quotient.lex_representative(self.id).clone()
+    }
+
+    // Every member of the class.
+    pub fn members(&self) -> &'a [Rig] {
+        self.quotient.class(self.id)
+    }
+
+    fn check_same(&self, other: &Element) {
+        assert!(
+            std::ptr::eq(self.quotient, other.quotient),
+            "elements of different quotients"
+        );
+    }
+}
+
+impl<'a> Add for Element<'a> {
+    type Output = Element<'a>;
+
+    fn add(self, other: Element<'a>) -> Element<'a> {
+        self.check_same(&other);
+        self.quotient.element(self.quotient.add(self.id, other.id))
+    }
+}
+
+impl<'a> Mul for Element<'a> {
+    type Output = Element<'a>;
+
+    fn mul(self, other: Element<'a>) -> Element<'a> {
+        self.check_same(&other);
+        self.quotient.element(self.quotient.mul(self.id, other.id))
+    }
+}
+
+impl PartialEq for Element<'_> {
+    fn eq(&self, other: &Element) -> bool {
+        std::ptr::eq(self.quotient, other.quotient) && self.id == other.id
+    }
+}
+
+impl Eq for Element<'_> {}
+
+impl Hash for Element<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+// Printed as the lexicographically-first member of the class.
+impl fmt::Display for Element<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.quotient.lex_representative(self.id))
+    }
+}
+
+impl fmt::Debug for Element<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Element({}: {})", self.id, self)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Quotient {
+    // Each class is sorted lexicographically, and the classes are
+    // sorted by size, then minimal element, for consistent output.
+    classes: Vec<Vec<Rig>>,
+    // The index into `classes` of each element, indexed by
+    // `Rig::to_int`.
+    class_ids: Vec<usize>,
+    // Cayley tables for the operations on class ids, indexed by
+    // `x * len + y`. The closure makes the equivalence a
+    // congruence, so it doesn't matter which elements of the classes
+    // are used to build them.
+    add_table: Vec<usize>,
+    mul_table: Vec<usize>,
+    // The closure's union-find, kept for the record of why things
+    // were merged.
+    union: RigUnion,
+    stats: ClosureStats,
+}
+
+impl Quotient {
+    pub fn new(union: RigUnion, stats: ClosureStats) -> Quotient {
+        let mut classes = union.get_classes();
+        let mut class_ids = vec![0; NUM_RIGS];
+        for (id, class) in classes.iter_mut().enumerate() {
+            class.sort();
+            for rig in class.iter() {
+                class_ids[rig.to_int()] = id;
+            }
+        }
+
+        let n = classes.len();
+        let mut add_table = Vec::with_capacity(n * n);
+        let mut mul_table = Vec::with_capacity(n * n);
+        for x in classes.iter() {
+            for y in classes.iter() {
+                add_table.push(class_ids[x[0].add(&y[0]).to_int()]);
+                mul_table.push(class_ids[x[0].mul(&y[0]).to_int()]);
+            }
+        }
+
+        Quotient {
+            classes,
+            class_ids,
+            add_table,
+            mul_table,
+            union,
+            stats,
+        }
+    }
+
+    // Number of elements of the quotient.
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub fn classes(&self) -> &[Vec<Rig>] {
+        &self.classes
+    }
+
+    pub fn class(&self, id: usize) -> &[Rig] {
+        &self.classes[id]
+    }
+
+    // The id of the class containing the given element. The fields of
+    // `Rig` are public, so it may not be in normal form, and
+    // coefficients of 4 or more would index the wrong element.
+    pub fn class_of(&self, rig: &Rig) -> usize {
+        self.class_ids[rig.normalise().to_int()]
+    }
+
+    // Lexicographically-first element of a class, according to Rust's
+    // auto-generated comparison operator.
+    pub fn lex_representative(&self, id: usize) -> &Rig {
+        &self.classes[id][0]
+    }
+
+    // "Smallest" element of a class, according to `cost`. Ties go to
+    // the lexicographically-first element.
+    pub fn small_representative(&self, id: usize) -> &Rig {
+        self.classes[id].iter().min_by_key(|x| cost(x)).unwrap()
+    }
+
+    // Find the class of any element.
+    pub fn lookup(&self, rig: &Rig) -> ClassInfo<'_> {
+        let id = self.class_of(rig);
+        ClassInfo {
+            id,
+            size: self.classes[id].len(),
+            lex: self.lex_representative(id),
+            small: self.small_representative(id),
+        }
+    }
+
+    // Apply an operation to class ids.
+    pub fn op(&self, op: Op, x: usize, y: usize) -> usize {
+        match op {
+            Op::Add => self.add(x, y),
+            Op::Mul => self.mul(x, y),
+        }
+    }
+
+    pub fn add(&self, x: usize, y: usize) -> usize {
+        self.add_table[x * self.len() + y]
+    }
+
+    pub fn mul(&self, x: usize, y: usize) -> usize {
+        self.mul_table[x * self.len() + y]
+    }
+
+    // The element with the given class id.
+    pub fn element(&self, id: usize) -> Element<'_> {
+        assert!(id < self.len(), "no class {}", id);
+        Element { quotient: self, id }
+    }
+
+    // The class of a `Rig`, as an element.
+    pub fn element_of(&self, rig: &Rig) -> Element<'_> {
+        self.element(self.class_of(rig))
+    }
+
+    // Every element, in order of class id.
+    pub fn elements(&self) -> impl Iterator<Item = Element<'_>> {
+        (0..self.len()).map(|id| self.element(id))
+    }
+
+    pub fn zero(&self) -> Element<'_> {
+        self.element_of(&ZERO)
+    }
+
+    pub fn one(&self) -> Element<'_> {
+        self.element_of(&ONE)
+    }
+
+    pub fn a(&self) -> Element<'_> {
+        self.element_of(&A)
+    }
+
+    pub fn b(&self) -> Element<'_> {
+        self.element_of(&B)
+    }
+
+    // Check the tables satisfy the axioms of an idempotent rig, by
+    // trying every combination of elements, returning the first
+    // counterexample found. This doesn't trust the closure at all, so
+    // is a check on the whole computation.
+    pub fn verify(&self) -> Result<(), String> {
+        let n = self.len();
+        let (zero, one) = (self.class_of(&ZERO), self.class_of(&ONE));
+        let show = |x: usize| self.lex_representative(x).to_string();
+        let fail = |law: &str, xs: &[usize]| {
+            let xs = xs.iter().map(|&x| show(x)).collect::<Vec<_>>();
+            Err(format!("{} fails for {}", law, xs.join(", ")))
+        };
+
+        for x in 0..n {
+            if self.add(zero, x) != x {
+                return fail("0 + x = x", &[x]);
+            }
+            if self.mul(zero, x) != zero || self.mul(x, zero) != zero {
+                return fail("0x = x0 = 0", &[x]);
+            }
+            if self.mul(one, x) != x || self.mul(x, one) != x {
+                return fail("1x = x1 = x", &[x]);
+            }
+            if self.mul(x, x) != x {
+                return fail("xx = x", &[x]);
+            }
+            for y in 0..n {
+                if self.add(x, y) != self.add(y, x) {
+                    return fail("x + y = y + x", &[x, y]);
+                }
+                let (xy_add, xy_mul) = (self.add(x, y), self.mul(x, y));
+                for z in 0..n {
+                    if self.add(xy_add, z) != self.add(x, self.add(y, z)) {
+                        return fail("(x + y) + z = x + (y + z)", &[x, y, z]);
+                    }
+                    if self.mul(xy_mul, z) != self.mul(x, self.mul(y, z)) {
+                        return fail("(xy)z = x(yz)", &[x, y, z]);
+                    }
+                    if self.mul(x, self.add(y, z)) != self.add(xy_mul, self.mul(x, z)) {
+                        return fail("x(y + z) = xy + xz", &[x, y, z]);
+                    }
+                    if self.mul(xy_add, z) != self.add(self.mul(x, z), self.mul(y, z)) {
+                        return fail("(x + y)z = xz + yz", &[x, y, z]);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // How the closure went.
+    pub fn stats(&self) -> &ClosureStats {
+        &self.stats
+    }
+
+    // The closure's record of merges, for explaining equalities.
+    pub fn union(&self) -> &RigUnion {
+        &self.union
+    }
+
+    // A chain of merges showing x and y are equal, or None if they
+    // aren't. See `RigUnion::explain`.
+    pub fn explain(&self, x: &Rig, y: &Rig) -> Option<Vec<Step>> {
+        self.union.explain(x, y)
+    }
+}
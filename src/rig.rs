@@ -0,0 +1,224 @@
+//
+// The elements of the free idempotent rig on two generators, before
+// any identification: sums of the seven words in a and b, with
+// coefficients reduced to 0..3.
+//
+
+use std::fmt;
+
+use crate::semiring::Semiring;
+
+pub const NUM_RIGS: usize = 4 * 4 * 4 * 4 * 4 * 4 * 4;
+
+// Implementation of a free rig with idempotency and two generators.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct Rig {
+    pub i: usize,
+    pub a: usize,
+    pub b: usize,
+    pub ab: usize,
+    pub ba: usize,
+    pub aba: usize,
+    pub bab: usize,
+}
+
+pub const ZERO: Rig = Rig {
+    i: 0,
+    a: 0,
+    b: 0,
+    ab: 0,
+    ba: 0,
+    aba: 0,
+    bab: 0,
+};
+
+pub const ONE: Rig = Rig { i: 1, ..ZERO };
+pub const A: Rig = Rig { a: 1, ..ZERO };
+pub const B: Rig = Rig { b: 1, ..ZERO };
+
+// The words in a and b that the fields of `Rig` count, in order. The
+// empty word is the identity.
+pub const WORDS: [&str; 7] = ["", "a", "b", "ab", "ba", "aba", "bab"];
+
+// The multiplication table of the words: WORD_PRODUCTS[x][y] is the
+// index in `WORDS` of the product of words x and y. In the free band,
+// a word equals any word with a square factor removed, e.g.
+// aba * bab = ababab = ab.
+pub const WORD_PRODUCTS: [[usize; 7]; 7] = [
+    // 1  a  b ab ba aba bab
+    [0, 1, 2, 3, 4, 5, 6], // 1
+    [1, 1, 3, 3, 5, 5, 3], // a
+    [2, 4, 2, 6, 4, 4, 6], // b
+    [3, 5, 3, 3, 5, 5, 3], // ab
+    [4, 4, 6, 6, 4, 4, 6], // ba
+    [5, 5, 3, 3, 5, 5, 3], // aba
+    [6, 4, 6, 6, 4, 4, 6], // bab
+];
+
+impl fmt::Display for Rig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == ZERO {
+            return write!(f, "0");
+        }
+
+        fn elt(acc: &mut Vec<String>, i: usize, s: &str) {
+            if i == 1 && !s.is_empty() {
+                acc.push(s.to_string());
+            } else if i != 0 {
+                acc.push(format!("{}{}", i, s));
+            }
+        }
+        let mut v = Vec::new();
+        elt(&mut v, self.i, "");
+        elt(&mut v, self.a, "a");
+        elt(&mut v, self.b, "b");
+        elt(&mut v, self.ab, "ab");
+        elt(&mut v, self.ba, "ba");
+        elt(&mut v, self.aba, "aba");
+        elt(&mut v, self.bab, "bab");
+        write!(f, "{}", v.join(" + "))
+    }
+}
+
+impl Rig {
+    // The set of rigs can be converted to/from numbers 0..NUM_RIGS.
+    pub fn from(i: usize) -> Rig {
+        Rig {
+            i: i & 3,
+            a: (i >> 2) & 3,
+            b: (i >> 4) & 3,
+            ab: (i >> 6) & 3,
+            ba: (i >> 8) & 3,
+            aba: (i >> 10) & 3,
+            bab: (i >> 12) & 3,
+        }
+    }
+
+    pub fn to_int(&self) -> usize {
+        self.i
+            + (self.a << 2)
+            + (self.b << 4)
+            + (self.ab << 6)
+            + (self.ba << 8)
+            + (self.aba << 10)
+            + (self.bab << 12)
+    }
+
+    // The coefficients of each word, in the order of `WORDS`.
+    pub fn coeffs(&self) -> [usize; 7] {
+        [self.i, self.a, self.b, self.ab, self.ba, self.aba, self.bab]
+    }
+
+    pub fn from_coeffs(c: [usize; 7]) -> Rig {
+        Rig {
+            i: c[0],
+            a: c[1],
+            b: c[2],
+            ab: c[3],
+            ba: c[4],
+            aba: c[5],
+            bab: c[6],
+        }
+    }
+
+    // The elements 1, a, b, ab, ba, aba, bab, as in `WORDS`.
+    pub fn basis() -> [Rig; 7] {
+        [
+            ONE,
+            A,
+            B,
+            Rig { ab: 1, ..ZERO },
+            Rig { ba: 1, ..ZERO },
+            Rig { aba: 1, ..ZERO },
+            Rig { bab: 1, ..ZERO },
+        ]
+    }
+
+    // The image of this element in any semiring, given the images of
+    // a and b, as in `Expr::eval`.
+    pub fn eval<S: Semiring>(&self, a: &S, b: &S) -> S {
+        let mut acc = a.zero();
+        for (w, c) in WORDS.iter().zip(self.coeffs()) {
+            let word = w
+                .chars()
+                .fold(a.one(), |acc, l| acc.mul(if l == 'a' { a } else { b }));
+            acc = acc.add(&a.nat(c).mul(&word));
+        }
+        acc
+    }
+
+    pub fn add(&self, other: &Rig) -> Rig {
+        Rig {
+            i: self.i + other.i,
+            a: self.a + other.a,
+            b: self.b + other.b,
+            ab: self.ab + other.ab,
+            ba: self.ba + other.ba,
+            aba: self.aba + other.aba,
+            bab: self.bab + other.bab,
+        }
+        .normalise()
+    }
+
+    pub fn mul(&self, other: &Rig) -> Rig {
+        Rig::from_coeffs(convolve(&self.coeffs(), &other.coeffs())).normalise()
+    }
+
+    // Due to idempotency, x + x = (x + x) * (x + x) = x + x + x + x,
+    // so we can always reduce 4x to 2x.
+    //
+    // Moreover, 4x y = 4 xy = 2 xy = 2x y, so always normalising down
+    // doesn't change the "reachable" elements.
+    pub fn normalise(&self) -> Rig {
+        Rig {
+            i: fold(self.i),
+            a: fold(self.a),
+            b: fold(self.b),
+            ab: fold(self.ab),
+            ba: fold(self.ba),
+            aba: fold(self.aba),
+            bab: fold(self.bab),
+        }
+    }
+}
+
+// Reduce a coefficient to 0..3, using 4 = 2, as in `Rig::normalise`.
+pub fn fold(i: usize) -> usize {
+    if i >= 4 {
+        i % 2 + 2
+    } else {
+        i
+    }
+}
+
+// Multiplication distributes over addition, so the product of two
+// sums of words is the sum of all the products of their words, as
+// given by `WORD_PRODUCTS`. This multiplies out coefficient vectors
+// without reducing the result, for the places that need the raw
+// counts.
+pub fn convolve(x: &[usize; 7], y: &[usize; 7]) -> [usize; 7] {
+    let mut acc = [0; 7];
+    for (row, xi) in WORD_PRODUCTS.iter().zip(x) {
+        for (&k, yj) in row.iter().zip(y) {
+            acc[k] += xi * yj;
+        }
+    }
+    acc
+}
+
+// A measure of how "small" an element is: the number of non-zero
+// coefficients, tie-broken on the sum of the coefficients.
+pub fn cost(r: &Rig) -> (usize, usize) {
+    let ci = if r.i != 0 { 1 } else { 0 };
+    let ca = if r.a != 0 { 1 } else { 0 };
+    let cb = if r.b != 0 { 1 } else { 0 };
+    let cab = if r.ab != 0 { 1 } else { 0 };
+    let cba = if r.ba != 0 { 1 } else { 0 };
+    let caba = if r.aba != 0 { 1 } else { 0 };
+    let cbab = if r.bab != 0 { 1 } else { 0 };
+
+    (
+        ci + ca + cb + cab + cba + caba + cbab,
+        r.i + r.a + r.b + r.ab + r.ba + r.aba + r.bab,
+    )
+}
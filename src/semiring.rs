@@ -0,0 +1,271 @@
+//
+// A common interface for the rigs around here, so that expressions
+// can be evaluated in any of them, e.g. to try out a candidate model
+// or homomorphism.
+//
+// Some rigs need more than the type to pin them down (the quotient's
+// tables, the size of a matrix), so `zero` and `one` are taken from an
+// existing element: `x.zero()` is the zero of the rig x lives in.
+//
+
+use std::fmt;
+
+use crate::quotient::Element;
+use crate::rig::Rig;
+
+pub trait Semiring: Clone + PartialEq {
+    fn zero(&self) -> Self;
+    fn one(&self) -> Self;
+    fn add(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+
+    // The natural number n, as 1 + 1 + ... + 1, computed by doubling.
+    fn nat(&self, mut n: usize) -> Self {
+        let mut acc = self.zero();
+        let mut pow = self.one();
+        while n > 0 {
+            if n & 1 == 1 {
+                acc = acc.add(&pow);
+            }
+            pow = pow.add(&pow);
+            n >>= 1;
+        }
+        acc
+    }
+}
+
+impl Semiring for Rig {
+    fn zero(&self) -> Rig {
+        crate::rig::ZERO
+    }
+
+    fn one(&self) -> Rig {
+        crate::rig::ONE
+    }
+
+    fn add(&self, other: &Rig) -> Rig {
+        Rig::add(self, other)
+    }
+
+    fn mul(&self, other: &Rig) -> Rig {
+        Rig::mul(self, other)
+    }
+}
+
+impl Semiring for Element<'_> {
+    fn zero(&self) -> Self {
+        self.quotient().zero()
+    }
+
+    fn one(&self) -> Self {
+        self.quotient().one()
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        *self + *other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        *self * *other
+    }
+}
+
+// The Booleans, with "or" and "and".
+impl Semiring for bool {
+    fn zero(&self) -> bool {
+        false
+    }
+
+    fn one(&self) -> bool {
+        true
+    }
+
+    fn add(&self, other: &bool) -> bool {
+        *self || *other
+    }
+
+    fn mul(&self, other: &bool) -> bool {
+        *self && *other
+    }
+}
+
+// The naturals, cut down to finitely many: past a threshold they
+// repeat with some period, so n = n + period for n >= threshold. With
+// period 1, anything past the threshold is cut down to it, and with
+// threshold 2 and period 2 this is 4 = 2, the coefficients of `Rig`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Truncated {
+    value: usize,
+    threshold: usize,
+    period: usize,
+}
+
+impl Truncated {
+    // The naturals 0..=max, where anything bigger than max is cut down
+    // to max.
+    pub fn new(value: usize, max: usize) -> Truncated {
+        Truncated::with_period(value, max, 1)
+    }
+
+    pub fn with_period(value: usize, threshold: usize, period: usize) -> Truncated {
+        assert!(period > 0, "the period must be positive");
+        let value = if value >= threshold {
+            threshold + (value - threshold) % period
+        } else {
+            value
+        };
+        Truncated {
+            value,
+            threshold,
+            period,
+        }
+    }
+
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    fn reduce(&self, value: usize) -> Truncated {
+        Truncated::with_period(value, self.threshold, self.period)
+    }
+}
+
+impl Semiring for Truncated {
+    fn zero(&self) -> Truncated {
+        self.reduce(0)
+    }
+
+    fn one(&self) -> Truncated {
+        self.reduce(1)
+    }
+
+    // The values are below threshold + period, so these can't
+    // overflow for any sensible choice of those.
+    fn add(&self, other: &Truncated) -> Truncated {
+        self.reduce(self.value + other.value)
+    }
+
+    fn mul(&self, other: &Truncated) -> Truncated {
+        self.reduce(self.value * other.value)
+    }
+}
+
+impl fmt::Display for Truncated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+// Square matrices over any semiring, stored row by row. There must be
+// at least one entry, to get the zero and one of the semiring from.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Matrix<S> {
+    size: usize,
+    entries: Vec<S>,
+}
+
+pub type BoolMatrix = Matrix<bool>;
+
+impl<S: Semiring> Matrix<S> {
+    pub fn new(size: usize, entries: Vec<S>) -> Matrix<S> {
+        assert!(size > 0, "matrices must have at least one entry");
+        assert_eq!(entries.len(), size * size, "wrong number of entries");
+        Matrix { size, entries }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> &S {
+        &self.entries[i * self.size + j]
+    }
+
+    pub fn entries(&self) -> &[S] {
+        &self.entries
+    }
+}
+
+impl BoolMatrix {
+    // Read a matrix written as rows of 0s and 1s separated by
+    // slashes, e.g. "10/11".
+    pub fn parse(s: &str) -> Option<BoolMatrix> {
+        let rows = s.split('/').collect::<Vec<_>>();
+        let size = rows.len();
+        let mut entries = Vec::new();
+        for row in rows {
+            if row.len() != size {
+                return None;
+            }
+            for c in row.chars() {
+                entries.push(match c {
+                    '0' => false,
+                    '1' => true,
+                    _ => return None,
+                });
+            }
+        }
+        Some(Matrix { size, entries })
+    }
+}
+
+impl<S: Semiring> Semiring for Matrix<S> {
+    fn zero(&self) -> Matrix<S> {
+        let zero = self.entries[0].zero();
+        Matrix::new(self.size, vec![zero; self.size * self.size])
+    }
+
+    fn one(&self) -> Matrix<S> {
+        let n = self.size;
+        let (zero, one) = (self.entries[0].zero(), self.entries[0].one());
+        let entries = (0..n * n).map(|k| {
+            if k / n == k % n {
+                one.clone()
+            } else {
+                zero.clone()
+            }
+        });
+        Matrix::new(n, entries.collect())
+    }
+
+    fn add(&self, other: &Matrix<S>) -> Matrix<S> {
+        assert_eq!(self.size, other.size, "matrices of different sizes");
+        let entries = self.entries.iter().zip(other.entries.iter());
+        Matrix::new(self.size, entries.map(|(x, y)| x.add(y)).collect())
+    }
+
+    fn mul(&self, other: &Matrix<S>) -> Matrix<S> {
+        assert_eq!(self.size, other.size, "matrices of different sizes");
+        let n = self.size;
+        let entries = (0..n * n)
+            .map(|k| {
+                (0..n)
+                    .map(|l| self.get(k / n, l).mul(other.get(l, k % n)))
+                    .fold(self.entries[0].zero(), |acc, x| acc.add(&x))
+            })
+            .collect();
+        Matrix::new(n, entries)
+    }
+}
+
+// Matrices are printed as rows separated by slashes, with each entry
+// a digit, e.g. "10/01".
+fn write_rows(f: &mut fmt::Formatter, size: usize, digits: Vec<String>) -> fmt::Result {
+    let rows = digits.chunks(size).map(|row| row.concat());
+    write!(f, "{}", rows.collect::<Vec<_>>().join("/"))
+}
+
+// Printed the way `parse` reads it.
+impl fmt::Display for BoolMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.entries.iter().map(|&x| (x as u8).to_string());
+        write_rows(f, self.size, digits.collect())
+    }
+}
+
+impl fmt::Display for Matrix<Truncated> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.entries.iter().map(|x| x.to_string());
+        write_rows(f, self.size, digits.collect())
+    }
+}
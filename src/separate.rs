@@ -0,0 +1,311 @@
+//
+// Proving classes distinct, by mapping the free rig into small
+// concrete idempotent rigs.
+//
+// The closure shows that elements are equal, but only shows that
+// classes are distinct in the sense that it didn't find a reason to
+// merge them. To prove it, map the free idempotent rig into some
+// other idempotent rig: if x and y map to different things, they
+// can't be equal. Since the rig is free, any choice of images for a
+// and b extends to a homomorphism, as long as the target really is
+// an idempotent rig.
+//
+// The targets here are square matrices over the Booleans, or over the
+// naturals with 4 = 2 (the coefficients of `Rig`), which are rigs but
+// not idempotent. However, the subrig generated by the images of a
+// and b may well be, and we check that directly: it's the sums of
+// products of the images, so we find the products, check they're
+// idempotent, find all their sums, and check those are idempotent
+// too.
+//
+// The arithmetic is all `Semiring`'s, with `Matrix` over `bool` or
+// `Truncated`, so all that's here is the search.
+//
+// Between them, a handful of these tell all 284 classes apart, which
+// shows the free idempotent rig has at least 284 elements, matching
+// the closure's upper bound.
+//
+
+use crate::quotient::Quotient;
+use crate::rig::{Rig, WORDS};
+use crate::semiring::{Matrix, Semiring, Truncated};
+
+// The entries of the matrices.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Coefficients {
+    // 0 and 1, with 1 + 1 = 1.
+    Boolean,
+    // 0..3, with 4 = 2, as in `Rig`.
+    Truncated,
+}
+
+impl Coefficients {
+    fn count(self) -> usize {
+        match self {
+            Coefficients::Boolean => 2,
+            Coefficients::Truncated => 4,
+        }
+    }
+}
+
+// The semirings the entries come from, with their elements numbered
+// 0..count, so that matrices can be enumerated and compared as lists
+// of numbers.
+trait Entry: Semiring {
+    fn from_index(i: u8) -> Self;
+    fn index(&self) -> u8;
+}
+
+impl Entry for bool {
+    fn from_index(i: u8) -> bool {
+        i == 1
+    }
+
+    fn index(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl Entry for Truncated {
+    fn from_index(i: u8) -> Truncated {
+        Truncated::with_period(i as usize, 2, 2)
+    }
+
+    fn index(&self) -> u8 {
+        self.value() as u8
+    }
+}
+
+// The rig of n by n matrices with the given coefficients.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Matrices {
+    pub size: usize,
+    pub coeffs: Coefficients,
+}
+
+impl Matrices {
+    // Every matrix, as its entries row by row, in order of the entries
+    // read as digits.
+    fn all(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let base = self.coeffs.count();
+        let entries = self.size * self.size;
+        (0..base.pow(entries as u32)).map(move |mut code| {
+            let mut m = vec![0; entries];
+            for entry in m.iter_mut() {
+                *entry = (code % base) as u8;
+                code /= base;
+            }
+            m
+        })
+    }
+
+    fn describe(&self) -> String {
+        let coeffs = match self.coeffs {
+            Coefficients::Boolean => "Boolean",
+            Coefficients::Truncated => "{0,1,2,3}",
+        };
+        format!("{}x{} {} matrices", self.size, self.size, coeffs)
+    }
+}
+
+// A homomorphism from the free idempotent rig into a matrix rig,
+// given by the images of a and b, as their entries row by row.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Witness {
+    pub matrices: Matrices,
+    pub a: Vec<u8>,
+    pub b: Vec<u8>,
+}
+
+// The most elements the image of a homomorphism can have, as a
+// quotient of the free rig. Anything bigger can't be idempotent.
+const MAX_IMAGE: usize = 284;
+
+fn to_matrix<S: Entry>(size: usize, m: &[u8]) -> Matrix<S> {
+    Matrix::new(size, m.iter().map(|&i| S::from_index(i)).collect())
+}
+
+fn from_matrix<S: Entry>(m: &Matrix<S>) -> Vec<u8> {
+    m.entries().iter().map(Entry::index).collect()
+}
+
+impl Witness {
+    fn images<S: Entry>(&self) -> (Matrix<S>, Matrix<S>) {
+        let size = self.matrices.size;
+        (to_matrix(size, &self.a), to_matrix(size, &self.b))
+    }
+
+    // Check that the subrig generated by the images of a and b is
+    // idempotent, so that this really is a homomorphism.
+    pub fn is_valid(&self) -> bool {
+        match self.matrices.coeffs {
+            Coefficients::Boolean => self.is_valid_in::<bool>(),
+            Coefficients::Truncated => self.is_valid_in::<Truncated>(),
+        }
+    }
+
+    fn is_valid_in<S: Entry>(&self) -> bool {
+        let (a, b) = self.images::<S>();
+
+        // The multiplicative monoid generated by a and b, which must
+        // be a band...
+        let mut products = vec![a.one()];
+        let mut next = 0;
+        while next < products.len() {
+            for g in [&a, &b] {
+                let p = products[next].mul(g);
+                if !products.contains(&p) {
+                    if products.len() == WORDS.len() || p.mul(&p) != p {
+                        return false;
+                    }
+                    products.push(p);
+                }
+            }
+            next += 1;
+        }
+
+        // ... and then all their sums must be idempotent too.
+        let mut sums = vec![a.zero()];
+        let mut next = 0;
+        while next < sums.len() {
+            if sums[next].mul(&sums[next]) != sums[next] {
+                return false;
+            }
+            for p in products.iter() {
+                let s = sums[next].add(p);
+                if !sums.contains(&s) {
+                    if sums.len() == MAX_IMAGE {
+                        return false;
+                    }
+                    sums.push(s);
+                }
+            }
+            next += 1;
+        }
+        true
+    }
+
+    // The image of an element, as its entries row by row.
+    pub fn eval(&self, x: &Rig) -> Vec<u8> {
+        match self.matrices.coeffs {
+            Coefficients::Boolean => {
+                let (a, b) = self.images::<bool>();
+                from_matrix(&x.eval(&a, &b))
+            }
+            Coefficients::Truncated => {
+                let (a, b) = self.images::<Truncated>();
+                from_matrix(&x.eval(&a, &b))
+            }
+        }
+    }
+
+    // Print a matrix, as rows separated by slashes, e.g. "10/01".
+    pub fn format(&self, m: &[u8]) -> String {
+        let size = self.matrices.size;
+        match self.matrices.coeffs {
+            Coefficients::Boolean => to_matrix::<bool>(size, m).to_string(),
+            Coefficients::Truncated => to_matrix::<Truncated>(size, m).to_string(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, a -> {}, b -> {}",
+            self.matrices.describe(),
+            self.format(&self.a),
+            self.format(&self.b)
+        )
+    }
+}
+
+// The matrix rigs to search, smallest first.
+const TARGETS: [Matrices; 5] = [
+    Matrices {
+        size: 1,
+        coeffs: Coefficients::Boolean,
+    },
+    Matrices {
+        size: 1,
+        coeffs: Coefficients::Truncated,
+    },
+    Matrices {
+        size: 2,
+        coeffs: Coefficients::Boolean,
+    },
+    Matrices {
+        size: 2,
+        coeffs: Coefficients::Truncated,
+    },
+    Matrices {
+        size: 3,
+        coeffs: Coefficients::Boolean,
+    },
+];
+
+// Every valid homomorphism into the target rigs that respects the
+// given relations, smallest first.
+pub fn witnesses(relations: &[(Rig, Rig)]) -> impl Iterator<Item = Witness> + '_ {
+    TARGETS
+        .iter()
+        .flat_map(|matrices| {
+            matrices.all().flat_map(move |a| {
+                matrices.all().map(move |b| Witness {
+                    matrices: *matrices,
+                    a: a.clone(),
+                    b,
+                })
+            })
+        })
+        .filter(Witness::is_valid)
+        .filter(|w| relations.iter().all(|(x, y)| w.eval(x) == w.eval(y)))
+}
+
+// Find a homomorphism under which x and y have different images. With
+// extra relations, only homomorphisms that respect them count, so
+// that x and y are distinct in the presented rig.
+pub fn separate(relations: &[(Rig, Rig)], x: &Rig, y: &Rig) -> Option<Witness> {
+    witnesses(relations).find(|w| w.eval(x) != w.eval(y))
+}
+
+// Find homomorphisms that, between them, separate every pair of
+// classes of the quotient, by refining a partition of the classes
+// until all the parts are singletons. Each homomorphism found splits
+// some part. If we run out of homomorphisms first, returns the parts
+// that couldn't be split. The quotient should have been computed with
+// the same relations.
+pub fn separate_all(
+    relations: &[(Rig, Rig)],
+    quotient: &Quotient,
+) -> Result<Vec<Witness>, Vec<Vec<usize>>> {
+    let reps = (0..quotient.len())
+        .map(|id| quotient.lex_representative(id))
+        .collect::<Vec<_>>();
+    let mut parts = vec![(0..quotient.len()).collect::<Vec<_>>()];
+    let mut family = Vec::new();
+    for w in witnesses(relations) {
+        if parts.iter().all(|part| part.len() == 1) {
+            return Ok(family);
+        }
+        let images = reps.iter().map(|r| w.eval(r)).collect::<Vec<_>>();
+        let mut refined = Vec::new();
+        for part in parts.iter() {
+            let mut split: Vec<(&[u8], Vec<usize>)> = Vec::new();
+            for &id in part.iter() {
+                match split.iter_mut().find(|(image, _)| *image == images[id]) {
+                    Some((_, ids)) => ids.push(id),
+                    None => split.push((&images[id], vec![id])),
+                }
+            }
+            refined.extend(split.into_iter().map(|(_, ids)| ids));
+        }
+        if refined.len() > parts.len() {
+            parts = refined;
+            family.push(w);
+        }
+    }
+    if parts.iter().all(|part| part.len() == 1) {
+        Ok(family)
+    } else {
+        Err(parts.into_iter().filter(|part| part.len() > 1).collect())
+    }
+}
@@ -0,0 +1,239 @@
+//
+// Checking that reducing coefficients doesn't change the answer.
+//
+// `Rig::normalise` reduces coefficients of 4 or more to 2 or 3, on the
+// grounds that x + x = (x + x)(x + x) = 4x in any idempotent rig. That
+// means the closure works in the rig with coefficients in N/(4 = 2)
+// rather than N, which is a shortcut worth checking.
+//
+// So here we run the same closure on sums with coefficients 0..bound,
+// with no reduction at all, relying on squares to make the
+// identifications. Sums and products can go out of range, in which
+// case we leave them out, so this is the closure of a partial
+// algebra. Then we check that:
+//
+//  * reducing coefficients maps each class into a single class of the
+//    quotient, and
+//  * the sums with coefficients 0..3 are equal exactly when they're
+//    in the same class of the quotient.
+//
+// Reducing coefficients respects + and *, so together these make the
+// quotient the same as the one we'd get without reducing.
+//
+// The closure of a partial algebra only gets bigger as the bound
+// does, since the bigger closure restricts to one on the smaller
+// range. So if some bound works, so do all the bigger ones. In
+// practice even 4 does, and every sum in range is shown equal to one
+// with coefficients 0..3, but we count them rather than assume it.
+//
+
+use std::collections::HashMap;
+
+use crate::quotient::Quotient;
+use crate::rig::{convolve, Rig, WORDS};
+use crate::union::UnionFind;
+
+// What the check found.
+#[derive(Clone, Debug)]
+pub struct Truncation {
+    // Coefficients run from 0 to bound - 1.
+    pub bound: usize,
+    // Number of sums with coefficients in range.
+    pub elements: usize,
+    // Number of pairs that merged two classes.
+    pub merges: usize,
+    // Number of equivalence classes among them.
+    pub classes: usize,
+    // Number of sums shown equal to one with coefficients 0..3.
+    pub reduced: usize,
+}
+
+// Marks a translation going out of range in the table of images. A
+// `u32` rather than an `Option<usize>` keeps the table to 4 bytes an
+// entry, which matters with bound^7 sums.
+const OUT_OF_RANGE: u32 = u32::MAX;
+
+// The sums with coefficients below the bound, numbered as digits in
+// base `bound`.
+struct Bounded {
+    bound: usize,
+}
+
+impl Bounded {
+    fn len(&self) -> usize {
+        self.bound.pow(WORDS.len() as u32)
+    }
+
+    fn encode(&self, c: &[usize; 7]) -> Option<usize> {
+        let mut idx = 0;
+        for &x in c.iter().rev() {
+            if x >= self.bound {
+                return None;
+            }
+            idx = idx * self.bound + x;
+        }
+        Some(idx)
+    }
+
+    fn decode(&self, mut idx: usize) -> [usize; 7] {
+        let mut c = [0; 7];
+        for x in c.iter_mut() {
+            *x = idx % self.bound;
+            idx /= self.bound;
+        }
+        c
+    }
+
+    // Multiply out, as in `Rig::mul`, but without reducing.
+    fn mul(&self, x: &[usize; 7], y: &[usize; 7]) -> Option<usize> {
+        self.encode(&convolve(x, y))
+    }
+
+    // Number of elementary translations: adding each word, and
+    // multiplying by each generator on either side.
+    fn translation_count(&self) -> usize {
+        WORDS.len() + 4
+    }
+
+    // The images of an element under the elementary translations, as
+    // in `closure.rs`, or None where they go out of range. These
+    // always come in the same order, so can be paired up.
+    fn translations(&self, idx: usize) -> Vec<Option<usize>> {
+        let x = self.decode(idx);
+        let mut v = Vec::new();
+        for w in 0..WORDS.len() {
+            let mut y = x;
+            y[w] += 1;
+            v.push(self.encode(&y));
+        }
+        for g in [1, 2] {
+            let mut g_coeffs = [0; 7];
+            g_coeffs[g] = 1;
+            v.push(self.mul(&g_coeffs, &x));
+            v.push(self.mul(&x, &g_coeffs));
+        }
+        v
+    }
+}
+
+// Run the closure with coefficients 0..bound, and compare it against
+// the quotient, which should have been computed with the same extra
+// relations.
+pub fn check_truncation(
+    quotient: &Quotient,
+    relations: &[(Rig, Rig)],
+    bound: usize,
+) -> Result<Truncation, String> {
+    if bound < 4 {
+        return Err("the bound must be at least 4, to include 0..3".to_string());
+    }
+    let bounded = Bounded { bound };
+    let count = bounded.translation_count();
+
+    // Sums can go out of range, so it isn't enough to translate the
+    // two sums that were merged, as `closure.rs` does: their images
+    // may be out of range where those of other members of their
+    // classes aren't. Instead each class keeps, for each translation,
+    // the image of any member that's in range, and when two classes
+    // merge, so do those images. Then the result doesn't depend on
+    // the order of the merges, and a bigger bound can only merge more.
+    let mut images = vec![OUT_OF_RANGE; bounded.len() * count];
+    for idx in 0..bounded.len() {
+        for (t, image) in bounded.translations(idx).into_iter().enumerate() {
+            if let Some(image) = image {
+                images[idx * count + t] = image as u32;
+            }
+        }
+    }
+
+    let mut union = UnionFind::new(bounded.len());
+    let mut worklist = Vec::new();
+    let mut merges = 0;
+
+    for idx in 0..bounded.len() {
+        let x = bounded.decode(idx);
+        if let Some(square) = bounded.mul(&x, &x) {
+            worklist.push((idx, square));
+        }
+    }
+    for (x, y) in relations.iter() {
+        let x = bounded.encode(&x.coeffs()).unwrap();
+        let y = bounded.encode(&y.coeffs()).unwrap();
+        worklist.push((x, y));
+    }
+    while let Some((x, y)) = worklist.pop() {
+        // Classes are keyed by their lowest member, so the merged
+        // class is keyed by the lower of the two.
+        let (x, y) = (union.find(x), union.find(y));
+        if !union.union(x, y) {
+            continue;
+        }
+        merges += 1;
+        let (keep, drop) = (x.min(y), x.max(y));
+        for t in 0..count {
+            let (i, j) = (images[keep * count + t], images[drop * count + t]);
+            if i == OUT_OF_RANGE {
+                images[keep * count + t] = j;
+            } else if j != OUT_OF_RANGE {
+                worklist.push((i as usize, j as usize));
+            }
+        }
+    }
+
+    // Each bounded class should reduce into a single class of the
+    // quotient, and the sums with coefficients 0..3 in each class of
+    // the quotient should all be in the same bounded class.
+    let reduce = |idx: usize| Rig::from_coeffs(bounded.decode(idx)).normalise();
+    let mut images = HashMap::new();
+    let mut preimages = vec![None; quotient.len()];
+    for idx in 0..bounded.len() {
+        let root = union.find(idx);
+        let rig = reduce(idx);
+        let id = quotient.class_of(&rig);
+        match images.get(&root) {
+            None => {
+                images.insert(root, id);
+            }
+            Some(&other) if other != id => {
+                return Err(format!(
+                    "{} and {} are equal, but reduce to different classes",
+                    Rig::from_coeffs(bounded.decode(root)),
+                    Rig::from_coeffs(bounded.decode(idx)),
+                ));
+            }
+            Some(_) => (),
+        }
+        if rig.coeffs() != bounded.decode(idx) {
+            continue;
+        }
+        match preimages[id] {
+            None => preimages[id] = Some(root),
+            Some(other) if other != root => {
+                return Err(format!(
+                    "{} and {} are in the same class of the quotient, but aren't equal",
+                    Rig::from_coeffs(bounded.decode(other)),
+                    rig,
+                ));
+            }
+            Some(_) => (),
+        }
+    }
+
+    // Everything in a class with a sum with coefficients 0..3 has been
+    // shown equal to it without reducing.
+    let mut is_reduced = vec![false; bounded.len()];
+    for root in preimages.into_iter().flatten() {
+        is_reduced[root] = true;
+    }
+    let reduced = (0..bounded.len())
+        .filter(|&idx| is_reduced[union.find(idx)])
+        .count();
+
+    Ok(Truncation {
+        bound,
+        elements: bounded.len(),
+        merges,
+        classes: union.class_count(),
+        reduced,
+    })
+}
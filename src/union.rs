@@ -0,0 +1,318 @@
+//
+// Union-find over the elements of the rig, used to build up the
+// equivalence classes.
+//
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::closure::Translation;
+use crate::rig::{Rig, NUM_RIGS};
+
+// Implement union-find ourselves, yet again. This time with union by
+// rank and path compression, so chains stay short.
+//
+// Which element ends up as the root of a class depends on the order
+// of unions, so we separately track the lowest index in each class,
+// and use that as the canonical representative.
+//
+// This works on plain indices, and can grow, for when we don't know
+// the set of elements up front. `RigUnion` wraps it up for `Rig`s.
+#[derive(Debug, Clone, Default)]
+pub struct UnionFind {
+    ptrs: Vec<usize>,
+    // Upper bound on the height of each root's tree.
+    ranks: Vec<u8>,
+    // Lowest index in the class, only meaningful at roots.
+    mins: Vec<usize>,
+    num_classes: usize,
+}
+
+impl UnionFind {
+    pub fn new(size: usize) -> UnionFind {
+        // Initially, all pointers point to themselves.
+        UnionFind {
+            ptrs: (0..size).collect::<Vec<_>>(),
+            ranks: vec![0; size],
+            mins: (0..size).collect::<Vec<_>>(),
+            num_classes: size,
+        }
+    }
+
+    // Add a new element, in a class of its own, returning its index.
+    pub fn push(&mut self) -> usize {
+        let idx = self.ptrs.len();
+        self.ptrs.push(idx);
+        self.ranks.push(0);
+        self.mins.push(idx);
+        self.num_classes += 1;
+        idx
+    }
+
+    // Number of elements.
+    pub fn len(&self) -> usize {
+        self.ptrs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ptrs.is_empty()
+    }
+
+    // Follow the pointers to the root of an index's tree.
+    fn root(&self, mut idx: usize) -> usize {
+        while self.ptrs[idx] != idx {
+            idx = self.ptrs[idx];
+        }
+        idx
+    }
+
+    // Find the root, and repoint everything on the way at it.
+    fn root_compress(&mut self, idx: usize) -> usize {
+        let root = self.root(idx);
+        let mut idx = idx;
+        while self.ptrs[idx] != root {
+            let tmp = self.ptrs[idx];
+            self.ptrs[idx] = root;
+            idx = tmp;
+        }
+        root
+    }
+
+    // The canonical representative of an index's class: the member
+    // with the lowest index.
+    pub fn find(&self, idx: usize) -> usize {
+        self.mins[self.root(idx)]
+    }
+
+    pub fn same_class(&self, idx1: usize, idx2: usize) -> bool {
+        self.root(idx1) == self.root(idx2)
+    }
+
+    // Number of equivalence classes so far.
+    pub fn class_count(&self) -> usize {
+        self.num_classes
+    }
+
+    // Merge the classes of two indices, returning whether they were
+    // previously in different classes.
+    pub fn union(&mut self, idx1: usize, idx2: usize) -> bool {
+        let root1 = self.root_compress(idx1);
+        let root2 = self.root_compress(idx2);
+        if root1 == root2 {
+            return false;
+        }
+
+        // Hang the shallower tree off the deeper one.
+        let (child, parent) = if self.ranks[root1] < self.ranks[root2] {
+            (root1, root2)
+        } else {
+            (root2, root1)
+        };
+        self.ptrs[child] = parent;
+        if self.ranks[child] == self.ranks[parent] {
+            self.ranks[parent] += 1;
+        }
+        self.mins[parent] = self.mins[parent].min(self.mins[child]);
+        self.num_classes -= 1;
+        true
+    }
+
+    // Group the indices into classes, each in increasing order, and
+    // ordered by their lowest index.
+    pub fn get_classes(&self) -> Vec<Vec<usize>> {
+        let mut sets: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..self.len() {
+            sets.entry(self.find(i)).or_default().push(i);
+        }
+        let mut classes = sets.into_values().collect::<Vec<_>>();
+        classes.sort();
+        classes
+    }
+}
+
+// Why two elements were merged.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Reason {
+    // The right-hand side is the square of the left-hand side.
+    Square,
+    // The extra relation with the given index.
+    Relation(usize),
+    // Applying the translation to both sides of the given merge.
+    Congruence(usize, Translation),
+}
+
+// A record of two elements being merged.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Merge {
+    pub lhs: Rig,
+    pub rhs: Rig,
+    pub reason: Reason,
+}
+
+// One step in an explanation: `from` equals `to` by the given merge,
+// used in one direction or the other.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub from: Rig,
+    pub to: Rig,
+    pub merge: usize,
+}
+
+// Union-find over all the normal-form `Rig`s.
+//
+// Every merge is recorded, along with why it happened. The merges form
+// a forest over the elements, with one tree per class, so there's a
+// unique path of merges between any two elements in the same class,
+// which explains why they're equal.
+#[derive(Debug, Clone)]
+pub struct RigUnion {
+    uf: UnionFind,
+    merges: Vec<Merge>,
+    // The merges involving each element, indexed by `Rig::to_int`.
+    edges: Vec<Vec<usize>>,
+}
+
+impl Default for RigUnion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The index of an element in the union-find. The fields of `Rig` are
+// public, so it may not be in normal form, and coefficients of 4 or
+// more would index the wrong element.
+fn index(r: &Rig) -> usize {
+    r.normalise().to_int()
+}
+
+impl RigUnion {
+    pub fn new() -> RigUnion {
+        RigUnion {
+            uf: UnionFind::new(NUM_RIGS),
+            merges: Vec::new(),
+            edges: vec![Vec::new(); NUM_RIGS],
+        }
+    }
+
+    // The canonical representative of an element's class: the member
+    // with the lowest index.
+    pub fn find(&self, r: &Rig) -> Rig {
+        Rig::from(self.uf.find(index(r)))
+    }
+
+    pub fn same_class(&self, r1: &Rig, r2: &Rig) -> bool {
+        self.uf.same_class(index(r1), index(r2))
+    }
+
+    // Number of equivalence classes so far.
+    pub fn class_count(&self) -> usize {
+        self.uf.class_count()
+    }
+
+    // Merge the classes of two elements, returning whether they were
+    // previously in different classes. If so, the merge is recorded
+    // with the given reason.
+    pub fn union(&mut self, r1: &Rig, r2: &Rig, reason: Reason) -> bool {
+        let (idx1, idx2) = (index(r1), index(r2));
+        if !self.uf.union(idx1, idx2) {
+            return false;
+        }
+        self.edges[idx1].push(self.merges.len());
+        self.edges[idx2].push(self.merges.len());
+        self.merges.push(Merge {
+            lhs: Rig::from(idx1),
+            rhs: Rig::from(idx2),
+            reason,
+        });
+        true
+    }
+
+    // All the merges, in the order they happened.
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    pub fn merge(&self, id: usize) -> &Merge {
+        &self.merges[id]
+    }
+
+    // A chain of merges leading from x to y, or None if they're not
+    // equal.
+    pub fn explain(&self, x: &Rig, y: &Rig) -> Option<Vec<Step>> {
+        if !self.same_class(x, y) {
+            return None;
+        }
+
+        // Breadth-first search from y, so that following the
+        // recorded steps back from x leads to y.
+        let (start, end) = (index(y), index(x));
+        let mut via: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(idx) = queue.pop_front() {
+            if idx == end {
+                break;
+            }
+            for &m in self.edges[idx].iter() {
+                let merge = &self.merges[m];
+                let (l, r) = (merge.lhs.to_int(), merge.rhs.to_int());
+                let next = if l == idx { r } else { l };
+                if next != start && !via.contains_key(&next) {
+                    via.insert(next, m);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut idx = end;
+        while idx != start {
+            let m = via[&idx];
+            let merge = &self.merges[m];
+            let next = if merge.lhs.to_int() == idx {
+                merge.rhs.to_int()
+            } else {
+                merge.lhs.to_int()
+            };
+            steps.push(Step {
+                from: Rig::from(idx),
+                to: Rig::from(next),
+                merge: m,
+            });
+            idx = next;
+        }
+        Some(steps)
+    }
+
+    // Every merge that the given merges depend on, including
+    // themselves, in the order they happened. Merges only depend on
+    // earlier merges, so this is an order they can be checked in.
+    pub fn dependencies(&self, merges: &[usize]) -> Vec<usize> {
+        let mut needed = vec![false; self.merges.len()];
+        let mut stack = merges.to_vec();
+        while let Some(m) = stack.pop() {
+            if needed[m] {
+                continue;
+            }
+            needed[m] = true;
+            if let Reason::Congruence(earlier, _) = self.merges[m].reason {
+                stack.push(earlier);
+            }
+        }
+        (0..self.merges.len()).filter(|&m| needed[m]).collect()
+    }
+
+    // Break our data structure down into an array of equivalence
+    // classes.
+    pub fn get_classes(&self) -> Vec<Vec<Rig>> {
+        // Sort by set size, then minimal value, to have consistent
+        // output.
+        let mut classes = self
+            .uf
+            .get_classes()
+            .into_iter()
+            .map(|x| x.into_iter().map(Rig::from).collect::<Vec<_>>())
+            .map(|x| (x.len(), x.iter().min().unwrap().clone(), x))
+            .collect::<Vec<_>>();
+        classes.sort();
+        classes.into_iter().map(|x| x.2).collect::<Vec<_>>()
+    }
+}